@@ -0,0 +1,13 @@
+fn main() {
+    use ls_option::ListOption;
+    use std::{
+        borrow::Cow,
+        path::{Path, PathBuf},
+    };
+    let option = ListOption::default();
+    dbg!(option.list("."));
+    dbg!(option.list(String::from(".")));
+    dbg!(option.list(Path::new(".")));
+    dbg!(option.list(PathBuf::from(".")));
+    dbg!(option.list(Cow::Borrowed(Path::new("."))));
+}
@@ -0,0 +1,12 @@
+fn main() {
+    use ls_option::ListOption;
+    let configured = vec![String::from("rs"), String::from("toml")];
+    dbg!(ListOption::default().exts(["rs", "toml"]).list("."));
+    dbg!(ListOption::default()
+        .exts(vec![String::from("rs")])
+        .list("."));
+    dbg!(ListOption::default().exts(&configured).list("."));
+    dbg!(ListOption::default()
+        .sufs(configured.iter().map(|ext| format!(".{}", ext)))
+        .list("."));
+}
@@ -0,0 +1,27 @@
+use ls_option::ListOption;
+
+struct RustFiles {
+    option: ListOption,
+}
+
+impl RustFiles {
+    fn new() -> Self {
+        Self {
+            option: ListOption::new()
+                .only_file()
+                .recursive(true)
+                .ext("rs")
+                .build(),
+        }
+    }
+
+    fn count_in(&self, path: &str) -> usize {
+        self.option.list(path).len()
+    }
+}
+
+fn main() {
+    let rust_files = RustFiles::new();
+    dbg!(rust_files.count_in("src"));
+    dbg!(rust_files.count_in("examples"));
+}
@@ -0,0 +1,223 @@
+use std::{
+    ffi::OsString,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use crate::{ListIter, ListOption, TryListIter};
+
+/// the state of a listing, taken by [`ListIter::checkpoint`] to go on later
+/// with [`ListOption::resume`], e.g. after the process restarts,
+/// serializable with the `serde` feature
+///
+/// it holds the directories left to read and the names left in the ones being read,
+/// not the entries already listed
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Checkpoint {
+    // when the listing started, so that the time filters don't shift, None if the root
+    // is not visited yet
+    pub(crate) started: Option<SystemTime>,
+    // the directories being read, from the root down
+    pub(crate) stack: Vec<DirCheckpoint>,
+    // the directory to be read next
+    pub(crate) pending: Option<DirCheckpoint>,
+    // the directories to read after, with breadth_first
+    pub(crate) queue: Vec<DirCheckpoint>,
+    // the paths and depths of the entries visited but not yielded yet
+    pub(crate) ready: Vec<(PathBuf, usize)>,
+    pub(crate) root_device: Option<u64>,
+    pub(crate) truncated_dirs: Vec<PathBuf>,
+    pub(crate) too_long_paths: Vec<PathBuf>,
+    pub(crate) hardlinks: Vec<(u64, u64)>,
+}
+
+// a directory in a checkpoint, see DirState
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct DirCheckpoint {
+    pub(crate) path: PathBuf,
+    pub(crate) depth: usize,
+    pub(crate) level: usize,
+    pub(crate) excluded: bool,
+    // if the entry of the directory itself is held
+    pub(crate) held: bool,
+    pub(crate) matched: bool,
+    // the number of entries read from it, for max_entries_per_dir
+    pub(crate) read: usize,
+    // the names of the entries left in it, the only ones visited when it is read again,
+    // None if it is not read yet
+    pub(crate) left: Option<Vec<OsString>>,
+}
+
+/// go on with a listing from a checkpoint
+impl ListOption {
+    /// lazily lists the paths at the given path left at the checkpoint, see [`ListIter`]
+    ///
+    /// the path and the options must be the ones of the listing the checkpoint is taken from,
+    /// then no path listed before the checkpoint is listed again,
+    /// while an entry created or removed after it may or may not be listed,
+    /// depending on whether its directory was read whole before the checkpoint
+    ///
+    /// max_results and timeout count from the resume, not from the start of the listing
+    pub fn resume<P>(&self, path: P, checkpoint: Checkpoint) -> ListIter<'_>
+    where
+        P: AsRef<Path>,
+    {
+        ListIter::resume(self, path, checkpoint)
+    }
+
+    /// the fallible version of [`ListOption::resume`], see [`TryListIter`]
+    pub fn try_resume<P>(&self, path: P, checkpoint: Checkpoint) -> TryListIter<'_>
+    where
+        P: AsRef<Path>,
+    {
+        TryListIter::resume(self, path, checkpoint)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use crate::{test_dir::TestDir, ListOption};
+
+    fn tree() -> TestDir {
+        let dir = TestDir::new();
+        for path in [
+            "a/1.txt",
+            "a/b/2.txt",
+            "a/b/c/3.txt",
+            "a/b/c/4.log",
+            "e/5.txt",
+            "e/f/6.log",
+            "7.txt",
+            ".h/8.txt",
+        ] {
+            dir.file(path, b"");
+        }
+        dir.file("empty/deeper/.keep", b"");
+        dir
+    }
+
+    // resume from every position, checking that the union is the full listing
+    fn resumes_from_anywhere(configure: impl Fn(&mut ListOption)) {
+        let dir = tree();
+        let mut option = ListOption::default();
+        configure(&mut option);
+        let full = option.list_paths(dir.path());
+        for half in 0..=full.len() {
+            let mut iter = option.iter(dir.path());
+            let mut listed: Vec<_> = iter.by_ref().take(half).collect();
+            let checkpoint = iter.checkpoint();
+            let mut fresh = ListOption::default();
+            configure(&mut fresh);
+            listed.extend(fresh.resume(dir.path(), checkpoint));
+            let unique: HashSet<_> = listed.iter().collect();
+            assert_eq!(unique.len(), listed.len(), "duplicates after {}", half);
+            assert_eq!(unique, full.iter().collect(), "after {}", half);
+        }
+    }
+
+    #[test]
+    fn resume_depth_first() {
+        resumes_from_anywhere(|option| {
+            option.recursive(true);
+        });
+    }
+
+    #[test]
+    fn resume_sorted_in_the_same_order() {
+        let dir = tree();
+        let mut option = ListOption::default();
+        option.recursive(true).hidden(true).sorted(true);
+        let full = option.list_paths(dir.path());
+        let mut iter = option.iter(dir.path());
+        let mut listed: Vec<_> = iter.by_ref().take(full.len() / 2).collect();
+        let checkpoint = iter.checkpoint();
+        listed.extend(option.resume(dir.path(), checkpoint));
+        assert_eq!(listed, full);
+    }
+
+    #[test]
+    fn resume_breadth_first() {
+        resumes_from_anywhere(|option| {
+            option.recursive(true).breadth_first(true);
+        });
+    }
+
+    #[test]
+    fn resume_contents_first_and_pruned() {
+        resumes_from_anywhere(|option| {
+            option.recursive(true).contents_first(true);
+        });
+        resumes_from_anywhere(|option| {
+            option.recursive(true).prune_empty_dirs(true).ext("log");
+        });
+        resumes_from_anywhere(|option| {
+            option
+                .recursive(true)
+                .dirs_with_matches_only(true)
+                .ext("log");
+        });
+    }
+
+    #[test]
+    fn resume_with_caps_and_levels() {
+        resumes_from_anywhere(|option| {
+            option.recursive(true).max_entries_per_dir(2);
+        });
+        resumes_from_anywhere(|option| {
+            option.level(2).dirs_first(true);
+        });
+    }
+
+    #[test]
+    fn checkpoint_twice() {
+        let dir = tree();
+        let mut option = ListOption::default();
+        option.recursive(true);
+        let full = option.list_paths(dir.path());
+        let mut iter = option.iter(dir.path());
+        let mut listed: Vec<_> = iter.by_ref().take(3).collect();
+        let mut iter = option.resume(dir.path(), iter.checkpoint());
+        listed.extend(iter.by_ref().take(3));
+        listed.extend(option.resume(dir.path(), iter.checkpoint()));
+        let unique: HashSet<_> = listed.iter().collect();
+        assert_eq!(unique.len(), listed.len());
+        assert_eq!(unique, full.iter().collect());
+    }
+
+    #[test]
+    fn listing_goes_on_after_a_checkpoint() {
+        let dir = tree();
+        let mut option = ListOption::default();
+        option.recursive(true);
+        let full = option.list_paths(dir.path());
+        let mut iter = option.iter(dir.path());
+        let mut listed: Vec<_> = iter.by_ref().take(4).collect();
+        iter.checkpoint();
+        listed.extend(iter);
+        assert_eq!(listed, full);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn resume_from_a_serialized_checkpoint() {
+        let dir = tree();
+        let mut option = ListOption::default();
+        option.recursive(true).hidden(true).max_entries_per_dir(3);
+        let full = option.list_paths(dir.path());
+        for half in 0..=full.len() {
+            let mut iter = option.iter(dir.path());
+            let mut listed: Vec<_> = iter.by_ref().take(half).collect();
+            let json = serde_json::to_string(&iter.checkpoint()).unwrap();
+            drop(iter);
+            let checkpoint = serde_json::from_str(&json).unwrap();
+            listed.extend(option.resume(dir.path(), checkpoint));
+            let unique: HashSet<_> = listed.iter().collect();
+            assert_eq!(unique.len(), listed.len(), "duplicates after {}", half);
+            assert_eq!(unique, full.iter().collect(), "after {}", half);
+        }
+    }
+}
@@ -0,0 +1,298 @@
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use memchr::memmem;
+
+#[cfg(feature = "infer")]
+use crate::name::push_unique;
+use crate::{Candidate, ListError, ListOption};
+
+/// filter files by their content, which is read only after all the other filters pass
+impl ListOption {
+    /// append one MIME type to the allowed content types, needs the `infer` feature
+    ///
+    /// only files whose content is detected as one of the allowed types will be listed,
+    /// by the magic bytes at their start whatever their name, e.g. content_type("image/png")
+    /// lists a PNG image named upload.dat, while directories are not filtered
+    ///
+    /// a file that can't be read fails with `ListError::ReadFile`, handled by the error policy
+    #[cfg(feature = "infer")]
+    pub fn content_type(&mut self, mime: &str) -> &mut Self {
+        push_unique(&mut self.content_types, mime);
+        self
+    }
+
+    /// set if only the text files will be listed, default false
+    ///
+    /// a file is binary when the sample at its start has a NUL byte, or when more than
+    /// a tenth of the sample isn't valid utf-8, and it is text otherwise,
+    /// so an empty file is text, see [`ListOption::content_sample_len`] for the sample
+    ///
+    /// directories are not filtered, and a file that can't be read
+    /// fails with `ListError::ReadFile`, handled by the error policy
+    pub fn only_text(&mut self, if_only: bool) -> &mut Self {
+        self.only_text = if_only;
+        self
+    }
+
+    /// set if only the binary files will be listed, default false,
+    /// see [`ListOption::only_text`]
+    pub fn only_binary(&mut self, if_only: bool) -> &mut Self {
+        self.only_binary = if_only;
+        self
+    }
+
+    /// list only files containing the bytes, like `grep -l`,
+    /// replacing the bytes set before, e.g. content_contains(b"example.com")
+    ///
+    /// the files are searched chunk by chunk, never loaded whole, and only when they pass
+    /// all the other filters, see [`ListOption::max_scan_bytes`] to bound the search,
+    /// while directories are not filtered
+    ///
+    /// a file that can't be read fails with `ListError::ReadFile`, handled by the error policy
+    pub fn content_contains(&mut self, pattern: &[u8]) -> &mut Self {
+        self.content_pattern = Some(pattern.to_vec());
+        self
+    }
+
+    /// list only files containing the string, see [`ListOption::content_contains`]
+    pub fn content_contains_str(&mut self, pattern: &str) -> &mut Self {
+        self.content_contains(pattern.as_bytes())
+    }
+
+    /// set the bytes searched from the start of each file by content_contains,
+    /// default unlimited, so a match beyond them is not found
+    pub fn max_scan_bytes(&mut self, limit: u64) -> &mut Self {
+        self.max_scan_bytes = Some(limit);
+        self
+    }
+
+    /// set the bytes read from the start of a file to tell text from binary, default 8192
+    pub fn content_sample_len(&mut self, len: u64) -> &mut Self {
+        self.sample_len = len;
+        self
+    }
+}
+
+// the checks of the content of a file
+impl ListOption {
+    pub(crate) fn show_content(&self, candidate: &Candidate) -> Result<bool, ListError> {
+        if !candidate.metadata.is_file() {
+            return Ok(true);
+        }
+        let read_error = |source| ListError::ReadFile {
+            path: candidate.path.to_path_buf(),
+            source,
+        };
+        if self.checks_head() {
+            let len = if self.only_text || self.only_binary {
+                self.sample_len.max(HEAD_LEN)
+            } else {
+                HEAD_LEN
+            };
+            let head = read_head(candidate.path, len).map_err(read_error)?;
+            if !(self.show_type(&head) && self.show_text(&head)) {
+                return Ok(false);
+            }
+        }
+        match &self.content_pattern {
+            Some(pattern) => {
+                contains(candidate.path, pattern, self.max_scan_bytes).map_err(read_error)
+            }
+            None => Ok(true),
+        }
+    }
+
+    // check if some filter needs the start of the files
+    fn checks_head(&self) -> bool {
+        #[cfg(feature = "infer")]
+        if !self.content_types.is_empty() {
+            return true;
+        }
+        self.only_text || self.only_binary
+    }
+
+    #[cfg(feature = "infer")]
+    fn show_type(&self, head: &[u8]) -> bool {
+        self.content_types.is_empty()
+            || infer::get(head).is_some_and(|kind| {
+                self.content_types
+                    .iter()
+                    .any(|mime| mime == kind.mime_type())
+            })
+    }
+
+    #[cfg(not(feature = "infer"))]
+    fn show_type(&self, _head: &[u8]) -> bool {
+        true
+    }
+
+    fn show_text(&self, head: &[u8]) -> bool {
+        let sample = &head[..head.len().min(self.sample_len as usize)];
+        let is_text = is_text(sample);
+        (!self.only_text || is_text) && (!self.only_binary || !is_text)
+    }
+}
+
+// the bytes read from the start of a file for the content filters
+const HEAD_LEN: u64 = 8192;
+
+// the heuristic of only_text: no NUL byte, and at most a tenth of the bytes not valid utf-8
+fn is_text(mut sample: &[u8]) -> bool {
+    if sample.contains(&0) {
+        return false;
+    }
+    let len = sample.len();
+    let mut invalid = 0;
+    while let Err(err) = std::str::from_utf8(sample) {
+        // a char cut at the end of the sample is fine
+        let Some(bad) = err.error_len() else {
+            break;
+        };
+        invalid += bad;
+        sample = &sample[err.valid_up_to() + bad..];
+    }
+    invalid * 10 <= len
+}
+
+// read at most len bytes from the start of the file
+fn read_head(path: &Path, len: u64) -> io::Result<Vec<u8>> {
+    let mut head = Vec::new();
+    File::open(path)?.take(len).read_to_end(&mut head)?;
+    Ok(head)
+}
+
+// the size of the chunks read while searching a file
+const CHUNK_LEN: usize = 64 * 1024;
+
+// search the file for the pattern chunk by chunk, reading at most limit bytes
+fn contains(path: &Path, pattern: &[u8], limit: Option<u64>) -> io::Result<bool> {
+    if pattern.is_empty() {
+        return Ok(true);
+    }
+    let finder = memmem::Finder::new(pattern);
+    let mut file = File::open(path)?.take(limit.unwrap_or(u64::MAX));
+    let mut buf = vec![0; CHUNK_LEN + pattern.len() - 1];
+    // the bytes kept from the previous chunk, so that a match across two chunks is found
+    let mut kept = 0;
+    loop {
+        let read = file.read(&mut buf[kept..])?;
+        if read == 0 {
+            return Ok(false);
+        }
+        let filled = kept + read;
+        if finder.find(&buf[..filled]).is_some() {
+            return Ok(true);
+        }
+        kept = filled.min(pattern.len() - 1);
+        buf.copy_within(filled - kept..filled, 0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{contains, is_text, CHUNK_LEN};
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn is_text_heuristic() {
+        assert!(is_text(b""));
+        assert!(is_text("plain text, café".as_bytes()));
+        assert!(!is_text(b"text\0with a nul"));
+        // one invalid byte in ten is still text, more is not
+        assert!(is_text(b"abcdefghi\xff"));
+        assert!(!is_text(b"abcdefgh\xff\xff"));
+        // a char cut at the end of the sample
+        assert!(is_text(&"é".as_bytes()[..1]));
+    }
+
+    #[test]
+    fn only_text_and_only_binary() {
+        let dir = TestDir::new();
+        dir.file("a.txt", b"hello");
+        dir.file("b.bin", b"\x00\x01\x02");
+        dir.file("empty", b"");
+        let mut late = b"x".repeat(100);
+        late.push(0);
+        dir.file("late.bin", &late);
+        dir.file("sub/c.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false).only_text(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a.txt", "empty", "sub", "sub/c.txt"]
+        );
+        // the NUL is beyond the sample
+        option.content_sample_len(50);
+        assert_eq!(option.count(dir.path()), 5);
+        option
+            .only_text(false)
+            .only_binary(true)
+            .content_sample_len(8192);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["b.bin", "late.bin", "sub"]
+        );
+    }
+
+    #[test]
+    fn contains_across_chunks() {
+        let dir = TestDir::new();
+        let mut content = vec![b'.'; CHUNK_LEN - 3];
+        content.extend_from_slice(b"needle");
+        content.extend(vec![b'.'; CHUNK_LEN]);
+        let path = dir.file("big", &content);
+        assert!(contains(&path, b"needle", None).unwrap());
+        assert!(contains(&path, b"", None).unwrap());
+        assert!(!contains(&path, b"needles", None).unwrap());
+        // the match ends beyond the limit
+        assert!(!contains(&path, b"needle", Some(CHUNK_LEN as u64)).unwrap());
+        assert!(contains(&path, b"needle", Some(CHUNK_LEN as u64 + 3)).unwrap());
+        assert!(contains(&dir.join("missing"), b"x", None).is_err());
+    }
+
+    #[test]
+    fn content_contains_lists_matching_files() {
+        let dir = TestDir::new();
+        dir.file("a.txt", b"see example.com");
+        dir.file("b.txt", b"nothing here");
+        dir.file("sub/c.txt", b"example.com");
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .content_contains_str("example.com");
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a.txt", "sub", "sub/c.txt"]
+        );
+        option.max_scan_bytes(12);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["sub", "sub/c.txt"]);
+    }
+
+    #[cfg(feature = "infer")]
+    #[test]
+    fn content_type_by_magic_bytes() {
+        let dir = TestDir::new();
+        dir.file("upload.dat", b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR");
+        dir.file("fake.png", b"not an image");
+        dir.file("sub/doc.pdf", b"%PDF-1.7\n");
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .content_type("image/png");
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["sub", "upload.dat"]
+        );
+        option.content_type("application/pdf");
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["sub", "sub/doc.pdf", "upload.dat"]
+        );
+    }
+}
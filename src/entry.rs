@@ -0,0 +1,229 @@
+use std::{
+    ffi::OsStr,
+    fs::{FileType, Metadata},
+    path::{Path, PathBuf},
+};
+
+use crate::metadata::is_junction;
+
+// the compound extensions known by Entry::compound_extension
+const COMPOUND_EXTS: &[&str] = &[
+    "tar.gz", "tar.bz2", "tar.xz", "tar.zst", "tar.lz", "tar.lz4", "tar.lzma", "tar.Z",
+];
+
+/// the kind of an entry, as the kind filters see it
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum EntryKind {
+    File,
+    Dir,
+    /// a symlink itself, whatever it points to, or a junction on windows,
+    /// see [`Entry::is_junction`] to tell them apart
+    Symlink,
+    /// a named pipe, only on unix
+    Fifo,
+    /// a unix domain socket, only on unix
+    Socket,
+    /// a block device, only on unix
+    BlockDevice,
+    /// a character device, only on unix
+    CharDevice,
+}
+
+impl EntryKind {
+    // the kind of what the metadata describes, None if it is none of the known kinds,
+    // which gives Symlink only for the metadata of a symlink itself
+    pub(crate) fn of(metadata: &Metadata) -> Option<EntryKind> {
+        let file_type = metadata.file_type();
+        if file_type.is_file() {
+            return Some(EntryKind::File);
+        }
+        if file_type.is_dir() {
+            return Some(EntryKind::Dir);
+        }
+        if file_type.is_symlink() {
+            return Some(EntryKind::Symlink);
+        }
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::FileTypeExt;
+            if file_type.is_fifo() {
+                return Some(EntryKind::Fifo);
+            }
+            if file_type.is_socket() {
+                return Some(EntryKind::Socket);
+            }
+            if file_type.is_block_device() {
+                return Some(EntryKind::BlockDevice);
+            }
+            if file_type.is_char_device() {
+                return Some(EntryKind::CharDevice);
+            }
+        }
+        None
+    }
+}
+
+/// an entry listed by a [`ListOption`](crate::ListOption), with what the traversal learned about it
+#[derive(Debug, Clone)]
+pub struct Entry {
+    pub(crate) path: PathBuf,
+    pub(crate) file_type: FileType,
+    pub(crate) depth: usize,
+    pub(crate) metadata: Metadata,
+}
+
+impl Entry {
+    /// the path of this entry, as it is listed
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// consume this entry, returning its path
+    pub fn into_path(self) -> PathBuf {
+        self.path
+    }
+
+    /// the file type of this entry itself, which tells a symlink from what it points to
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// the kind of this entry itself, so a symlink is `EntryKind::Symlink`
+    /// whatever it points to
+    pub fn kind(&self) -> EntryKind {
+        if self.file_type.is_symlink() {
+            EntryKind::Symlink
+        } else {
+            EntryKind::of(&self.metadata).unwrap_or(EntryKind::File)
+        }
+    }
+
+    /// the extension of the file name of this entry, same as `Path::extension`
+    pub fn extension(&self) -> Option<&OsStr> {
+        self.path.extension()
+    }
+
+    /// the extension of the file name of this entry, in the longest known compound form,
+    /// e.g. `tar.gz` for backup.tar.gz, falls back to [`Entry::extension`] for other names
+    pub fn compound_extension(&self) -> Option<&OsStr> {
+        let name = self.path.file_name()?.to_str();
+        COMPOUND_EXTS
+            .iter()
+            .filter_map(|ext| {
+                let stem = name?.strip_suffix(ext)?.strip_suffix('.')?;
+                (!stem.is_empty()).then_some(OsStr::new(*ext))
+            })
+            .max_by_key(|ext| ext.len())
+            .or_else(|| self.extension())
+    }
+
+    /// check if this entry is a junction, listed as `EntryKind::Symlink` like a symlink,
+    /// only on windows, where the reparse tag of a symlink is fetched for it each time
+    pub fn is_junction(&self) -> bool {
+        self.file_type.is_symlink() && is_junction(&self.path)
+    }
+
+    /// the depth of this entry relative to the listing root, the root itself has depth 0
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// the metadata of this entry, following symlinks unless `follow_symlinks(false)`
+    pub fn metadata(&self) -> &Metadata {
+        &self.metadata
+    }
+}
+
+impl AsRef<Path> for Entry {
+    fn as_ref(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_dir::TestDir, EntryKind, ListOption};
+
+    #[test]
+    fn extensions_of_an_entry() {
+        let dir = TestDir::new();
+        for path in [
+            "backup.tar.gz",
+            "notes.gz",
+            ".tar.gz",
+            "Makefile",
+            "a.tar.zst",
+        ] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option.include_root(false).hidden(true).sorted(true);
+        let exts: Vec<_> = option
+            .entries(dir.path())
+            .iter()
+            .map(|entry| {
+                let ext = |ext: Option<&std::ffi::OsStr>| {
+                    ext.map(|ext| ext.to_string_lossy().into_owned())
+                };
+                (ext(entry.extension()), ext(entry.compound_extension()))
+            })
+            .collect();
+        let some = |ext: &str| Some(ext.to_string());
+        assert_eq!(
+            exts,
+            [
+                // .tar.gz has no stem before tar
+                (some("gz"), some("gz")),
+                (None, None),
+                (some("zst"), some("tar.zst")),
+                (some("gz"), some("tar.gz")),
+                (some("gz"), some("gz")),
+            ]
+        );
+    }
+
+    #[test]
+    fn entries_keep_what_the_traversal_learned() {
+        let dir = TestDir::new();
+        dir.file("sub/a.txt", b"abc");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        let entries = option.entries(dir.path());
+        assert_eq!(entries.len(), 2);
+        let file = entries
+            .iter()
+            .find(|entry| entry.kind() == EntryKind::File)
+            .unwrap();
+        assert_eq!(file.path(), dir.join("sub/a.txt"));
+        assert_eq!(file.depth(), 2);
+        assert_eq!(file.metadata().len(), 3);
+        assert!(file.file_type().is_file() && !file.is_junction());
+        assert_eq!(AsRef::<std::path::Path>::as_ref(file), file.path());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlink_entries_are_symlinks_whatever_they_point_to() {
+        let dir = TestDir::new();
+        dir.file("a.txt", b"abc");
+        dir.symlink("a.txt", "link");
+        let mut option = ListOption::default();
+        option.include_root(false);
+        let entries = option.entries(dir.path());
+        let link = entries
+            .iter()
+            .find(|entry| entry.path().ends_with("link"))
+            .unwrap();
+        assert_eq!(link.kind(), EntryKind::Symlink);
+        assert!(link.file_type().is_symlink());
+        // the metadata follows it
+        assert_eq!(link.metadata().len(), 3);
+        option.follow_symlinks(false);
+        let entries = option.entries(dir.path());
+        let link = entries
+            .iter()
+            .find(|entry| entry.path().ends_with("link"))
+            .unwrap();
+        assert!(link.metadata().file_type().is_symlink());
+    }
+}
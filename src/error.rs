@@ -0,0 +1,258 @@
+use std::{
+    error::Error,
+    fmt, io,
+    path::{Path, PathBuf},
+};
+
+/// what to do when some entry can't be read while listing
+///
+/// an entry removed by someone else while it is listed is not an error, whatever the policy,
+/// it is skipped as if it was never there
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    /// ignore the error and go on with the rest of the tree
+    Skip,
+    /// stop listing at the first error
+    #[default]
+    Abort,
+    /// keep the error and go on with the rest of the tree
+    Collect,
+}
+
+/// the error returned by the fallible listing apis
+///
+/// more variants may come, as some exist only with a feature, e.g. Git with `git`,
+/// so a match on it needs a wildcard arm
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum ListError {
+    /// a directory or one of its entries can't be read
+    ReadDir { path: PathBuf, source: io::Error },
+    /// the metadata of a path can't be fetched
+    Metadata { path: PathBuf, source: io::Error },
+    /// a file can't be read for the content filters
+    ReadFile { path: PathBuf, source: io::Error },
+    /// the path doesn't exist
+    NotFound { path: PathBuf },
+    /// the git repository of the listing root can't be read for git_tracked
+    #[cfg(feature = "git")]
+    Git {
+        path: PathBuf,
+        source: crate::GitError,
+    },
+    /// the option can never list anything
+    Config(ConfigError),
+    /// an io error without a known path
+    Io(io::Error),
+}
+
+impl ListError {
+    /// the path that failed, if known
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            ListError::ReadDir { path, .. }
+            | ListError::Metadata { path, .. }
+            | ListError::ReadFile { path, .. }
+            | ListError::NotFound { path } => Some(path),
+            #[cfg(feature = "git")]
+            ListError::Git { path, .. } => Some(path),
+            ListError::Config(_) | ListError::Io(_) => None,
+        }
+    }
+
+    // check if the error comes from a path which the process may not read
+    pub(crate) fn is_permission_denied(&self) -> bool {
+        match self {
+            ListError::ReadDir { source, .. }
+            | ListError::Metadata { source, .. }
+            | ListError::ReadFile { source, .. } => {
+                source.kind() == io::ErrorKind::PermissionDenied
+            }
+            _ => false,
+        }
+    }
+
+    // check if the error comes from a path which doesn't exist (anymore)
+    pub(crate) fn is_not_found(&self) -> bool {
+        match self {
+            ListError::ReadDir { source, .. }
+            | ListError::Metadata { source, .. }
+            | ListError::ReadFile { source, .. } => source.kind() == io::ErrorKind::NotFound,
+            _ => false,
+        }
+    }
+}
+
+// check if the error of following a symlink means that it points to nothing,
+// i.e. to a missing path, or to itself through a loop like `ln -s loop loop`
+pub(crate) fn is_dangling(err: &io::Error) -> bool {
+    #[cfg(unix)]
+    let loop_code = Some(libc::ELOOP);
+    // ERROR_CANT_RESOLVE_FILENAME
+    #[cfg(windows)]
+    let loop_code = Some(1921);
+    #[cfg(not(any(unix, windows)))]
+    let loop_code = None;
+    err.kind() == io::ErrorKind::NotFound
+        || (loop_code.is_some() && err.raw_os_error() == loop_code)
+}
+
+impl fmt::Display for ListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ListError::ReadDir { path, source } => {
+                write!(f, "failed to read directory {}: {}", path.display(), source)
+            }
+            ListError::Metadata { path, source } => {
+                write!(
+                    f,
+                    "failed to get metadata of {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            ListError::ReadFile { path, source } => {
+                write!(f, "failed to read file {}: {}", path.display(), source)
+            }
+            ListError::NotFound { path } => write!(f, "{} not found", path.display()),
+            #[cfg(feature = "git")]
+            ListError::Git { path, source } => {
+                write!(
+                    f,
+                    "failed to read the git repository of {}: {}",
+                    path.display(),
+                    source
+                )
+            }
+            ListError::Config(err) => write!(f, "invalid option: {}", err),
+            ListError::Io(source) => write!(f, "io error: {}", source),
+        }
+    }
+}
+
+impl Error for ListError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            ListError::ReadDir { source, .. }
+            | ListError::Metadata { source, .. }
+            | ListError::ReadFile { source, .. }
+            | ListError::Io(source) => Some(source),
+            ListError::Config(err) => Some(err),
+            #[cfg(feature = "git")]
+            ListError::Git { source, .. } => Some(source),
+            ListError::NotFound { .. } => None,
+        }
+    }
+}
+
+impl From<io::Error> for ListError {
+    fn from(source: io::Error) -> Self {
+        ListError::Io(source)
+    }
+}
+
+impl From<ConfigError> for ListError {
+    fn from(err: ConfigError) -> Self {
+        ListError::Config(err)
+    }
+}
+
+/// a contradictory combination of options which can never list anything,
+/// see [`ListOption::validate`](crate::ListOption::validate)
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ConfigError {
+    /// both `file(false)` and `dir(false)`
+    NoKind,
+    /// both `hidden(false)` and `unhidden(false)`
+    NoVisibility,
+    /// `level(0)`, recursive or not
+    ZeroLevel,
+    /// `min_depth(n)` deeper than the level
+    MinDepthBeyondLevel,
+    /// suffix filters, which only apply to files, with `file(false)`
+    SufsWithoutFile,
+    /// ext filters with `no_extension(true)`
+    ExtsWithNoExtension,
+    /// both `only_empty_files(true)` and `skip_empty_files(true)`
+    EmptyAndNonEmpty,
+    /// both `only_readonly(true)` and `skip_readonly(true)`
+    ReadonlyAndNotReadonly,
+    /// both `only_text(true)` and `only_binary(true)`
+    TextAndBinary,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let msg = match self {
+            ConfigError::NoKind => "file(false) and dir(false) show neither files nor directories",
+            ConfigError::NoVisibility => {
+                "hidden(false) and unhidden(false) show neither hidden nor unhidden entries"
+            }
+            ConfigError::ZeroLevel => "level(0) lists no level at all",
+            ConfigError::MinDepthBeyondLevel => "min_depth is deeper than any level listed",
+            ConfigError::SufsWithoutFile => {
+                "suffix filters only apply to files, which file(false) never shows"
+            }
+            ConfigError::ExtsWithNoExtension => {
+                "ext filters and no_extension(true) can't both match a file name"
+            }
+            ConfigError::EmptyAndNonEmpty => {
+                "only_empty_files(true) and skip_empty_files(true) show no file at all"
+            }
+            ConfigError::ReadonlyAndNotReadonly => {
+                "only_readonly(true) and skip_readonly(true) show no file at all"
+            }
+            ConfigError::TextAndBinary => {
+                "only_text(true) and only_binary(true) show no file at all"
+            }
+        };
+        f.write_str(msg)
+    }
+}
+
+impl Error for ConfigError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_tell_the_path_and_the_operation() {
+        let denied = || io::Error::from(io::ErrorKind::PermissionDenied);
+        let path = PathBuf::from("some/dir");
+        let err = ListError::ReadDir {
+            path: path.clone(),
+            source: denied(),
+        };
+        assert_eq!(err.path(), Some(path.as_path()));
+        assert!(err.to_string().starts_with("failed to read directory some"));
+        assert!(err.source().is_some());
+        assert!(err.is_permission_denied() && !err.is_not_found());
+        let err = ListError::NotFound { path: path.clone() };
+        assert!(err.to_string().ends_with("dir not found"));
+        assert!(err.source().is_none() && !err.is_not_found());
+        let err = ListError::from(ConfigError::ZeroLevel);
+        assert_eq!(err.path(), None);
+        assert_eq!(
+            err.to_string(),
+            "invalid option: level(0) lists no level at all"
+        );
+        assert!(err.source().is_some());
+        let err = ListError::from(denied());
+        assert!(matches!(err, ListError::Io(_)) && err.path().is_none());
+        // only the errors with a path are ones to skip by their kind
+        assert!(!err.is_permission_denied());
+    }
+
+    #[test]
+    fn dangling_means_missing_or_looping() {
+        assert!(is_dangling(&io::Error::from(io::ErrorKind::NotFound)));
+        assert!(!is_dangling(&io::Error::from(
+            io::ErrorKind::PermissionDenied
+        )));
+        #[cfg(unix)]
+        assert!(is_dangling(&io::Error::from_raw_os_error(libc::ELOOP)));
+        #[cfg(windows)]
+        assert!(is_dangling(&io::Error::from_raw_os_error(1921)));
+    }
+}
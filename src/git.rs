@@ -0,0 +1,186 @@
+use std::{
+    collections::HashSet,
+    path::{Path, PathBuf},
+};
+
+use git2::Repository;
+
+use crate::ListOption;
+
+pub use git2::Error as GitError;
+
+/// filter entries by their git status, needs the `git` feature
+impl ListOption {
+    /// set if only the entries tracked by git, or only the untracked ones, will be listed
+    ///
+    /// the repository enclosing the listing root is found once when a listing starts,
+    /// and its index tells the tracked files, a directory being tracked when some file under it is,
+    /// e.g. glob("**/*.rs").git_tracked(false) finds the stray .rs files, while ignored files
+    /// count as untracked, see respect_gitignore of the `ignore` feature to skip them
+    ///
+    /// a submodule is listed as a tracked entry but never descended into, and neither is `.git`,
+    /// while a root outside any repository fails with `ListError::Git` in the fallible apis,
+    /// and lists nothing in the others
+    pub fn git_tracked(&mut self, if_tracked: bool) -> &mut Self {
+        self.git_tracked = Some(if_tracked);
+        self
+    }
+}
+
+// the status of an entry in the repository of the listing root
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum GitStatus {
+    Tracked,
+    Untracked,
+    // a tracked submodule, which is not descended into
+    Submodule,
+    // the git directory of the repository, which is never listed
+    GitDir,
+}
+
+// what the index of the repository told when the listing started
+pub(crate) struct GitIndex {
+    // the path of the listing root relative to the working tree
+    prefix: PathBuf,
+    // the tracked files and the directories above them, relative to the working tree
+    tracked: HashSet<PathBuf>,
+    submodules: HashSet<PathBuf>,
+    git_dir: Option<PathBuf>,
+}
+
+impl GitIndex {
+    pub(crate) fn open(root: &Path) -> Result<Self, GitError> {
+        let repo = Repository::discover(root)?;
+        let workdir = repo
+            .workdir()
+            .ok_or_else(|| GitError::from_str("the repository has no working tree"))?;
+        let workdir = canonicalize(workdir)?;
+        let prefix = canonicalize(root)?
+            .strip_prefix(&workdir)
+            .map_err(|_| GitError::from_str("the path is outside the working tree"))?
+            .to_path_buf();
+        let git_dir = canonicalize(repo.path())
+            .ok()
+            .and_then(|dir| dir.strip_prefix(&workdir).ok().map(Path::to_path_buf));
+        let mut tracked = HashSet::new();
+        let mut submodules = HashSet::new();
+        for entry in repo.index()?.iter() {
+            let Some(path) = to_path(&entry.path) else {
+                continue;
+            };
+            // a gitlink, i.e. a submodule
+            if entry.mode & 0o170000 == 0o160000 {
+                submodules.insert(path.clone());
+            }
+            tracked.extend(path.ancestors().map(Path::to_path_buf));
+        }
+        Ok(Self {
+            prefix,
+            tracked,
+            submodules,
+            git_dir,
+        })
+    }
+
+    // the status of the entry at this path relative to the listing root, None for the root
+    pub(crate) fn status(&self, rel_path: Option<&Path>) -> GitStatus {
+        let path = match rel_path {
+            Some(rel) => self.prefix.join(rel),
+            None => self.prefix.clone(),
+        };
+        if self.git_dir.as_ref() == Some(&path) {
+            GitStatus::GitDir
+        } else if self.submodules.contains(&path) {
+            GitStatus::Submodule
+        } else if self.tracked.contains(&path) {
+            GitStatus::Tracked
+        } else {
+            GitStatus::Untracked
+        }
+    }
+}
+
+impl GitStatus {
+    // check if the entry is shown by git_tracked
+    pub(crate) fn shows(self, if_tracked: bool) -> bool {
+        match self {
+            GitStatus::Tracked | GitStatus::Submodule => if_tracked,
+            GitStatus::Untracked => !if_tracked,
+            GitStatus::GitDir => false,
+        }
+    }
+}
+
+fn canonicalize(path: &Path) -> Result<PathBuf, GitError> {
+    path.canonicalize()
+        .map_err(|err| GitError::from_str(&format!("{}: {}", path.display(), err)))
+}
+
+// the path of an index entry, which git stores as bytes with `/` separators
+#[cfg(unix)]
+fn to_path(bytes: &[u8]) -> Option<PathBuf> {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    Some(PathBuf::from(OsStr::from_bytes(bytes)))
+}
+
+#[cfg(not(unix))]
+fn to_path(bytes: &[u8]) -> Option<PathBuf> {
+    std::str::from_utf8(bytes).ok().map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use git2::{Repository, Signature};
+
+    use crate::{test_dir::TestDir, ListError, ListOption};
+
+    fn commit(repo: &Repository, path: &str) {
+        let mut index = repo.index().unwrap();
+        index.add_path(Path::new(path)).unwrap();
+        index.write().unwrap();
+        let tree = repo.find_tree(index.write_tree().unwrap()).unwrap();
+        let signature = Signature::now("test", "test@example.com").unwrap();
+        repo.commit(Some("HEAD"), &signature, &signature, "init", &tree, &[])
+            .unwrap();
+    }
+
+    #[test]
+    fn tracked_and_untracked_files() {
+        let dir = TestDir::new();
+        let repo = Repository::init(dir.path()).unwrap();
+        dir.file("src/tracked.rs", b"");
+        dir.file("src/stray.rs", b"");
+        commit(&repo, "src/tracked.rs");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false).hidden(true);
+        assert_eq!(
+            dir.rel(option.git_tracked(true).list_paths(dir.path())),
+            ["src", "src/tracked.rs"]
+        );
+        assert_eq!(
+            dir.rel(option.git_tracked(false).list_paths(dir.path())),
+            ["src/stray.rs"]
+        );
+    }
+
+    #[test]
+    fn root_outside_a_repository() {
+        let dir = TestDir::new();
+        dir.file("a.rs", b"");
+        let mut option = ListOption::default();
+        option.git_tracked(true);
+        if Repository::discover(dir.path()).is_ok() {
+            // the temp dir is itself in a repository
+            return;
+        }
+        assert!(matches!(
+            option.try_list(dir.path()),
+            Err(ListError::Git { .. })
+        ));
+        assert!(option.list_paths(dir.path()).is_empty());
+        assert_eq!(option.count(dir.path()), 0);
+    }
+}
@@ -0,0 +1,307 @@
+use std::path::{Path, PathBuf};
+
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+
+use crate::ListOption;
+
+pub use globset::Error as GlobError;
+
+// the glob patterns set on a ListOption, compiled as they are set
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Globs {
+    patterns: Vec<String>,
+    set: GlobSet,
+    // the same patterns ignoring the case
+    nocase_set: GlobSet,
+}
+
+impl PartialEq for Globs {
+    fn eq(&self, other: &Self) -> bool {
+        self.patterns == other.patterns
+    }
+}
+
+impl Eq for Globs {}
+
+impl Globs {
+    fn set<I, S>(&mut self, patterns: I) -> Result<(), GlobError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let patterns: Vec<String> = patterns
+            .into_iter()
+            .map(|p| p.as_ref().to_string())
+            .collect();
+        let mut builder = GlobSetBuilder::new();
+        let mut nocase_builder = GlobSetBuilder::new();
+        for pattern in &patterns {
+            // `*` stops at `/`, only `**` spans directories
+            let mut glob = GlobBuilder::new(pattern);
+            glob.literal_separator(true);
+            builder.add(glob.build()?);
+            nocase_builder.add(glob.case_insensitive(true).build()?);
+        }
+        self.set = builder.build()?;
+        self.nocase_set = nocase_builder.build()?;
+        self.patterns = patterns;
+        Ok(())
+    }
+
+    pub(crate) fn is_match(&self, rel_path: &Path, ignore_case: bool) -> bool {
+        self.patterns.is_empty() || self.is_any_match(rel_path, ignore_case)
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        self.patterns.is_empty()
+    }
+
+    // unlike is_match, no pattern matches nothing
+    pub(crate) fn is_any_match(&self, rel_path: &Path, ignore_case: bool) -> bool {
+        if ignore_case {
+            self.nocase_set.is_match(rel_path)
+        } else {
+            self.set.is_match(rel_path)
+        }
+    }
+
+    // check if some pattern may match a path under the directory,
+    // by the leading components of the patterns without any wildcard
+    pub(crate) fn may_match_under(&self, dir: &Path, ignore_case: bool) -> bool {
+        let folded;
+        let dir = if ignore_case {
+            folded = PathBuf::from(dir.to_string_lossy().to_lowercase());
+            &folded
+        } else {
+            dir
+        };
+        self.patterns.iter().any(|pattern| {
+            let pattern = if ignore_case {
+                pattern.to_lowercase()
+            } else {
+                pattern.clone()
+            };
+            let mut literal = PathBuf::new();
+            for component in pattern.split('/') {
+                if component.contains(['*', '?', '[', '{', '\\']) {
+                    return literal.starts_with(dir) || dir.starts_with(&literal);
+                }
+                literal.push(component);
+            }
+            literal.starts_with(dir) && literal != dir
+        })
+    }
+}
+
+/// filter entries by glob patterns, needs the `glob` feature
+impl ListOption {
+    /// append one glob pattern, only entries whose path relative to the listing root
+    /// matches one of the patterns will be listed, the root itself is never matched
+    ///
+    /// `*` and `?` don't match `/`, while `**` spans directories,
+    /// e.g. glob("**/fixtures/*.json") lists a/fixtures/b.json but not a/fixtures/c/d.json
+    ///
+    /// the pattern is compiled here, so an invalid one fails now instead of while listing
+    pub fn glob(&mut self, pattern: &str) -> Result<&mut Self, GlobError> {
+        let patterns: Vec<String> = self
+            .globs
+            .patterns
+            .iter()
+            .cloned()
+            .chain([pattern.to_string()])
+            .collect();
+        self.globs.set(patterns)?;
+        Ok(self)
+    }
+
+    /// replace the glob patterns with these, see [`ListOption::glob`]
+    pub fn globs<I, S>(&mut self, patterns: I) -> Result<&mut Self, GlobError>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.globs.set(patterns)?;
+        Ok(self)
+    }
+
+    /// append one glob pattern rescuing the entries it matches from the excludes,
+    /// like a `!` line in a `.gitignore`
+    ///
+    /// excludes win over the include filters, and reincludes win over the excludes,
+    /// so the entries matched are listed even if excluded by exclude_ext, exclude_suf,
+    /// exclude_dir, exclude_path, prune_if or the ignore files, or if they are under a directory
+    /// excluded so, as long as they still pass the include filters like ext or glob
+    ///
+    /// e.g. exclude_dir("target") with reinclude("target/doc/**/*.html") lists the html files
+    /// of target/doc, without listing target or its other entries,
+    /// which means an excluded directory is read when some pattern may match under it
+    ///
+    /// the pattern is relative to the listing root and follows the syntax of [`ListOption::glob`]
+    pub fn reinclude(&mut self, pattern: &str) -> Result<&mut Self, GlobError> {
+        let patterns: Vec<String> = self
+            .reincludes
+            .patterns
+            .iter()
+            .cloned()
+            .chain([pattern.to_string()])
+            .collect();
+        self.reincludes.set(patterns)?;
+        Ok(self)
+    }
+
+    /// the glob patterns set by [`ListOption::glob`]
+    pub fn glob_patterns(&self) -> &[String] {
+        &self.globs.patterns
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::Globs;
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn globs_match_relative_paths() {
+        let dir = TestDir::new();
+        for path in [
+            "a/fixtures/b.json",
+            "a/fixtures/c/d.json",
+            "fixtures/e.json",
+            "f.json",
+        ] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .only_file()
+            .glob("**/fixtures/*.json")
+            .unwrap();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a/fixtures/b.json", "fixtures/e.json"]
+        );
+        option.glob("*.json").unwrap();
+        assert_eq!(option.glob_patterns(), ["**/fixtures/*.json", "*.json"]);
+        assert_eq!(option.count(dir.path()), 3);
+        option
+            .globs(["a/**"])
+            .unwrap()
+            .case_insensitive(true)
+            .glob("F.JSON")
+            .unwrap();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a/fixtures/b.json", "a/fixtures/c/d.json", "f.json"]
+        );
+    }
+
+    #[test]
+    fn single_chars_and_classes() {
+        let dir = TestDir::new();
+        for path in [
+            "test_1.rs",
+            "test_22.rs",
+            "a.txt",
+            "b.txt",
+            "c.txt",
+            "sub/test_3.rs",
+        ] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .only_file()
+            .glob("test_?.rs")
+            .unwrap();
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["test_1.rs"]);
+        option.globs(["[ab].txt"]).unwrap();
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a.txt", "b.txt"]);
+        option.globs(["[!ab].txt"]).unwrap();
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["c.txt"]);
+        // ? never matches a separator, while ** spans directories
+        option.globs(["sub?test_3.rs"]).unwrap();
+        assert!(option.list_paths(dir.path()).is_empty());
+        option.globs(["**/test_[0-9].rs"]).unwrap();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["sub/test_3.rs", "test_1.rs"]
+        );
+    }
+
+    #[test]
+    fn globs_combine_with_the_suffix_filters() {
+        let dir = TestDir::new();
+        for path in [
+            "src/main.rs",
+            "src/lib.rs.bak",
+            "src/main_test.rs",
+            "build.rs",
+            "a.toml",
+        ] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option.recursive(true).only_file().glob("**/*.rs*").unwrap();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            [
+                "build.rs",
+                "src/lib.rs.bak",
+                "src/main.rs",
+                "src/main_test.rs"
+            ]
+        );
+        // an entry has to pass both
+        option.sufs([".rs"]);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["build.rs", "src/main.rs", "src/main_test.rs"]
+        );
+        option.exclude_suf("_test.rs");
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["build.rs", "src/main.rs"]
+        );
+        option.clear_sufs().ext("toml");
+        assert!(option.list_paths(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn invalid_globs_fail_when_set() {
+        let mut option = ListOption::default();
+        assert!(option.glob("a/[b").is_err());
+        assert!(option.reinclude("{a").is_err());
+        assert!(option.glob_patterns().is_empty());
+        assert_eq!(option, ListOption::default());
+    }
+
+    #[test]
+    fn may_match_under_by_the_literal_prefix() {
+        let mut globs = Globs::default();
+        globs.set(["target/doc/**/*.html", "src/*.rs"]).unwrap();
+        for (dir, expected) in [
+            ("target", true),
+            ("target/doc", true),
+            ("target/doc/deep/er", true),
+            ("target/debug", false),
+            ("src", true),
+            ("tests", false),
+        ] {
+            assert_eq!(
+                globs.may_match_under(Path::new(dir), false),
+                expected,
+                "{}",
+                dir
+            );
+        }
+        assert!(!globs.may_match_under(Path::new("Target"), false));
+        assert!(globs.may_match_under(Path::new("Target"), true));
+        globs.set(["docs/readme.md"]).unwrap();
+        assert!(globs.may_match_under(Path::new("docs"), false));
+        assert!(!globs.may_match_under(Path::new("docs/readme.md"), false));
+    }
+}
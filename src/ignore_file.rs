@@ -0,0 +1,225 @@
+use std::path::Path;
+#[cfg(feature = "ignore")]
+use std::sync::Arc;
+
+#[cfg(feature = "ignore")]
+use ignore::{
+    gitignore::{Gitignore, GitignoreBuilder},
+    Match,
+};
+
+#[cfg(feature = "ignore")]
+use crate::name::push_unique;
+use crate::ListOption;
+
+// the ignore rules loaded from a directory and its ancestors in the listing,
+// the deepest directory first
+#[cfg(feature = "ignore")]
+#[derive(Clone)]
+pub(crate) struct Ignores(Option<Arc<IgnoreNode>>);
+
+#[cfg(feature = "ignore")]
+struct IgnoreNode {
+    matcher: Gitignore,
+    parent: Ignores,
+}
+
+#[cfg(feature = "ignore")]
+impl Ignores {
+    // the rules above the root, which ignore nothing
+    pub(crate) fn root() -> Ignores {
+        Ignores(None)
+    }
+
+    // the rules for the entries of the directory, adding the ones from its ignore files
+    pub(crate) fn enter(&self, option: &ListOption, dir: &Path) -> Ignores {
+        let gitignore = option.respect_gitignore.then_some(".gitignore");
+        let names = gitignore
+            .into_iter()
+            .chain(option.ignore_filenames.iter().map(String::as_str));
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut found = false;
+        // the files are added in order, so the later ones win in the same directory
+        for name in names {
+            let file = dir.join(name);
+            if file.is_file() {
+                // a malformed line is skipped, the rest of the file still applies
+                let _ = builder.add(file);
+                found = true;
+            }
+        }
+        match builder.build() {
+            Ok(matcher) if found => Ignores(Some(Arc::new(IgnoreNode {
+                matcher,
+                parent: self.clone(),
+            }))),
+            _ => self.clone(),
+        }
+    }
+
+    // check if the path is ignored, the rules of deeper directories win
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut node = &self.0;
+        while let Some(ignores) = node {
+            match ignores.matcher.matched(path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => node = &ignores.parent.0,
+            }
+        }
+        false
+    }
+}
+
+/// filter entries by ignore files, needs the `ignore` feature
+#[cfg(feature = "ignore")]
+impl ListOption {
+    /// set if the `.gitignore` files found in the root and the directories under it are respected,
+    /// default false
+    ///
+    /// the ignored entries are neither listed nor descended into,
+    /// with the usual gitignore syntax, including negated patterns like `!keep.log`
+    /// and directory only patterns like `build/`
+    ///
+    /// the rules of a `.gitignore` apply to the entries under its directory,
+    /// the ones above the root are not read
+    pub fn respect_gitignore(&mut self, if_respect: bool) -> &mut Self {
+        self.respect_gitignore = if_respect;
+        self
+    }
+
+    /// add the name of ignore files to respect, in the gitignore syntax,
+    /// e.g. add_ignore_filename(".prettierignore")
+    ///
+    /// they are loaded like `.gitignore` with [`ListOption::respect_gitignore`],
+    /// the rules of a deeper directory win over the ones of its ancestors,
+    /// and in the same directory, the files added later win over `.gitignore` and the ones before
+    ///
+    /// the ignore files themselves are listed by the other filters, as any other file,
+    /// unless they ignore themselves
+    pub fn add_ignore_filename(&mut self, name: &str) -> &mut Self {
+        push_unique(&mut self.ignore_filenames, name);
+        self
+    }
+}
+
+// stands for the ignore rules without the `ignore` feature, which never ignore anything
+#[cfg(not(feature = "ignore"))]
+#[derive(Clone)]
+pub(crate) struct Ignores;
+
+#[cfg(not(feature = "ignore"))]
+impl Ignores {
+    pub(crate) fn root() -> Ignores {
+        Ignores
+    }
+
+    pub(crate) fn enter(&self, _option: &ListOption, _dir: &Path) -> Ignores {
+        Ignores
+    }
+
+    pub(crate) fn is_ignored(&self, _path: &Path, _is_dir: bool) -> bool {
+        false
+    }
+}
+
+#[cfg(all(test, feature = "ignore"))]
+mod tests {
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn gitignore_rules_per_directory() {
+        let dir = TestDir::new();
+        dir.file(".gitignore", b"*.log\n!keep.log\nbuild/\n");
+        dir.file("a.log", b"");
+        dir.file("keep.log", b"");
+        dir.file("build/out.o", b"");
+        dir.file("src/build", b"");
+        dir.file("src/.gitignore", b"!b.log\n*.rs\n");
+        dir.file("src/b.log", b"");
+        dir.file("src/main.rs", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false).only_file();
+        assert_eq!(option.count(dir.path()), 6);
+        option.respect_gitignore(true).hidden(true);
+        // build/ only ignores directories, and the deeper rules win
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            [
+                ".gitignore",
+                "keep.log",
+                "src/.gitignore",
+                "src/b.log",
+                "src/build"
+            ]
+        );
+        // the rules above the root are not read
+        assert_eq!(
+            dir.rel(option.list_paths(dir.join("src"))),
+            ["src/.gitignore", "src/b.log", "src/build"]
+        );
+    }
+
+    #[test]
+    fn custom_ignore_files_win_in_order() {
+        let dir = TestDir::new();
+        dir.file(".gitignore", b"*.tmp\n");
+        dir.file(".myignore", b"!a.tmp\n*.md\n.myignore\n");
+        dir.file(".ignore", b"!b.md\n");
+        dir.file("a.tmp", b"");
+        dir.file("b.tmp", b"");
+        dir.file("b.md", b"");
+        dir.file("c.md", b"");
+        let mut option = ListOption::default();
+        option
+            .include_root(false)
+            .respect_gitignore(true)
+            .add_ignore_filename(".myignore");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a.tmp"]);
+        option.add_ignore_filename(".ignore").hidden(true);
+        // the ignore files are listed unless they ignore themselves
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            [".gitignore", ".ignore", "a.tmp", "b.md"]
+        );
+        // without respect_gitignore, only the files added are read
+        option.respect_gitignore(false);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            [".gitignore", ".ignore", "a.tmp", "b.md", "b.tmp"]
+        );
+    }
+
+    #[test]
+    fn nested_custom_ignore_files() {
+        let dir = TestDir::new();
+        dir.file(".myignore", b"*.log\n");
+        dir.file("a.log", b"");
+        dir.file("src/.myignore", b"!keep.log\n");
+        dir.file("src/keep.log", b"");
+        dir.file("src/drop.log", b"");
+        dir.file("src/deep/keep.log", b"");
+        dir.file("src/deep/main.rs", b"");
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .only_file()
+            .add_ignore_filename(".myignore");
+        // the child file re-includes what the parent ignores, in its own subtree only
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["src/deep/keep.log", "src/deep/main.rs", "src/keep.log"]
+        );
+        // a listing rooted at the child reads its rules only
+        assert_eq!(
+            dir.rel(option.list_paths(dir.join("src"))),
+            [
+                "src/deep/keep.log",
+                "src/deep/main.rs",
+                "src/drop.log",
+                "src/keep.log"
+            ]
+        );
+    }
+}
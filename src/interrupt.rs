@@ -0,0 +1,162 @@
+use std::{
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use crate::ListOption;
+
+/// stop a listing before it ends
+impl ListOption {
+    /// set the flag which stops the listings once it is set, e.g. from another thread
+    /// when the listing is no longer wanted, replacing the flag set before
+    ///
+    /// the flag is checked before each entry and each directory read, and a stopped listing
+    /// is not an error, so every api returns the entries found so far, `try_list` included
+    pub fn cancel_token(&mut self, token: Arc<AtomicBool>) -> &mut Self {
+        self.cancel = Some(CancelToken(token));
+        self
+    }
+
+    /// set the time a listing may take, counted from its start, e.g. over a network mount
+    /// which may hang
+    ///
+    /// the time is checked before each directory read, and also every 16 entries read
+    /// in a directory, so that a huge directory with slow filters can't outlast it,
+    /// a single slow read or filter call is not cut, so the listing stops at most
+    /// 16 entries after the time is up
+    ///
+    /// a listing past its time is truncated, not failed, so every api returns the entries
+    /// found so far, `try_list` included, see [`ListOption::try_list_report`]
+    /// and [`TryListIter::timed_out`](crate::TryListIter::timed_out) to tell if it ended so
+    pub fn timeout(&mut self, timeout: Duration) -> &mut Self {
+        self.timeout = Some(timeout);
+        self
+    }
+}
+
+// the flag set by cancel_token, two tokens are equal only if they are the same flag
+#[derive(Clone, Debug)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancelToken {}
+
+// the checks of a listing going on
+impl ListOption {
+    // check if the listing should stop now
+    pub(crate) fn is_interrupted(&self) -> bool {
+        self.cancel
+            .as_ref()
+            .is_some_and(|token| token.0.load(Ordering::Relaxed))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{
+            atomic::{AtomicBool, AtomicUsize, Ordering},
+            Arc, Barrier,
+        },
+        thread,
+        time::{Duration, Instant},
+    };
+
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn timeout_cuts_slow_filters() {
+        let dir = TestDir::new();
+        for i in 0..100 {
+            dir.file(&format!("{}.txt", i), b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .include_root(false)
+            .timeout(Duration::from_millis(20))
+            .filter_fn(|_| {
+                thread::sleep(Duration::from_millis(5));
+                true
+            });
+        let start = Instant::now();
+        let mut iter = option.try_iter(dir.path());
+        let listed = iter.by_ref().count();
+        assert!(start.elapsed() < Duration::from_millis(400));
+        // the time is up after 4 entries, and checked after 16
+        assert!(listed <= 16);
+        assert!(iter.timed_out());
+        // try_list returns what it found, only the report tells it timed out
+        assert!(option.try_list(dir.path()).unwrap().len() <= 16);
+        let (paths, truncation) = option.try_list_report(dir.path()).unwrap();
+        assert!(paths.len() <= 16 && truncation.timed_out());
+    }
+
+    #[test]
+    fn no_timeout_when_the_listing_ends_in_time() {
+        let dir = TestDir::new();
+        dir.file("a.txt", b"");
+        let mut option = ListOption::default();
+        option.timeout(Duration::from_secs(60));
+        let mut iter = option.try_iter(dir.path());
+        assert_eq!(iter.by_ref().count(), 2);
+        assert!(!iter.timed_out());
+    }
+
+    // cancel a recursive listing from the test thread at its 100th entry, while it waits
+    // in a filter, and return the entries it found, run on another thread
+    fn cancel_midway<T: Send>(list: impl FnOnce(&ListOption) -> T + Send) -> T {
+        let token = Arc::new(AtomicBool::new(false));
+        let reached = Arc::new(Barrier::new(2));
+        let resumed = Arc::new(Barrier::new(2));
+        let seen = AtomicUsize::new(0);
+        let mut option = ListOption::default();
+        option.recursive(true).cancel_token(token.clone());
+        let (reached_in, resumed_in) = (reached.clone(), resumed.clone());
+        option.filter_fn(move |_| {
+            if seen.fetch_add(1, Ordering::Relaxed) == 100 {
+                reached_in.wait();
+                resumed_in.wait();
+            }
+            true
+        });
+        thread::scope(|scope| {
+            let listing = scope.spawn(|| list(&option));
+            reached.wait();
+            token.store(true, Ordering::Relaxed);
+            resumed.wait();
+            listing.join().unwrap()
+        })
+    }
+
+    #[test]
+    fn cancelled_listing_returns_what_it_found() {
+        let dir = TestDir::new();
+        for i in 0..4000 {
+            dir.file(&format!("{}/{}.txt", i % 20, i), b"");
+        }
+        let root = dir.path();
+        let mut option = ListOption::default();
+        option.recursive(true);
+        assert_eq!(option.count(root), 4021);
+        // the entry being checked is still listed, then nothing is read
+        let listed = cancel_midway(|option| option.list(root));
+        assert_eq!(listed.len(), 101);
+        let listed = cancel_midway(|option| option.try_list(root).unwrap());
+        assert_eq!(listed.len(), 101);
+        // a token set before the listing lists nothing
+        let token = Arc::new(AtomicBool::new(true));
+        assert!(option
+            .cancel_token(token)
+            .try_list(root)
+            .unwrap()
+            .is_empty());
+    }
+}
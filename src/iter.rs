@@ -0,0 +1,1169 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashSet, VecDeque},
+    ffi::OsString,
+    fs::{self, DirEntry, FileType, ReadDir},
+    io, iter,
+    path::{Path, PathBuf},
+    time::Instant,
+    vec,
+};
+
+#[cfg(feature = "git")]
+use crate::git::{GitIndex, GitStatus};
+use crate::{
+    checkpoint::{Checkpoint, DirCheckpoint},
+    error::is_dangling,
+    ignore_file::Ignores,
+    metadata::{file_id, hardlink_id, ListStart},
+    mounts::PseudoFs,
+    name::NameFilters,
+    Candidate, Entry, ErrorPolicy, ListError, ListOption, Truncation,
+};
+
+/// a lazy iterator over the paths listed by a [`ListOption`]
+///
+/// it yields the same paths as [`ListOption::list_paths`], in the same order,
+/// but a directory is only read when the iterator is advanced into it,
+/// so stopping early (e.g. with `take`) avoids scanning the rest of the tree
+///
+/// at most 64 directories are kept open at once, the ones above being read whole
+/// before going deeper, so that a deep tree doesn't run out of file descriptors
+///
+/// a directory it has no permission to read is still listed itself, with its entries skipped,
+/// like any other path it may not read, a root which doesn't exist lists nothing,
+/// as does a root outside any repository with git_tracked,
+/// and an entry whose metadata can't be fetched is skipped, while it panics on the other errors,
+/// unless the error policy is `ErrorPolicy::Skip`, see [`TryListIter`] for the fallible version
+pub struct ListIter<'a> {
+    inner: TryListIter<'a>,
+}
+
+impl<'a> ListIter<'a> {
+    pub(crate) fn new<P>(option: &'a ListOption, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            inner: TryListIter {
+                walker: Walker::infallible(option, path),
+            },
+        }
+    }
+
+    pub(crate) fn resume<P>(option: &'a ListOption, path: P, checkpoint: Checkpoint) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            inner: TryListIter {
+                walker: Walker::infallible(option, path).resume(checkpoint),
+            },
+        }
+    }
+
+    /// take the state of the listing, to go on from here with [`ListOption::resume`]
+    ///
+    /// the rest of the directories being read is read then, to note the names left in them,
+    /// and the listing goes on with what was read
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.inner.checkpoint()
+    }
+}
+
+impl Iterator for ListIter<'_> {
+    type Item = PathBuf;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(expect_listed)
+    }
+}
+
+/// the fallible version of [`ListIter`]
+///
+/// an error is yielded in place of a directory that can't be read,
+/// what happens after it depends on the [`ErrorPolicy`] of the option
+pub struct TryListIter<'a> {
+    walker: Walker<'a>,
+}
+
+impl<'a> TryListIter<'a> {
+    pub(crate) fn new<P>(option: &'a ListOption, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            walker: Walker::new(option, path),
+        }
+    }
+
+    pub(crate) fn resume<P>(option: &'a ListOption, path: P, checkpoint: Checkpoint) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            walker: Walker::new(option, path).resume(checkpoint),
+        }
+    }
+
+    /// take the state of the listing, to go on from here with [`ListOption::try_resume`],
+    /// see [`ListIter::checkpoint`]
+    pub fn checkpoint(&mut self) -> Checkpoint {
+        self.walker.checkpoint()
+    }
+
+    // the listing of ListOption::try_list_report
+    pub(crate) fn reporting<P>(option: &'a ListOption, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            walker: Walker {
+                reports: true,
+                ..Walker::new(option, path)
+            },
+        }
+    }
+
+    // the limits hit by the listing, walking on to tell if anything is left after max_results
+    pub(crate) fn into_truncation(mut self) -> Truncation {
+        let walker = &mut self.walker;
+        let mut truncation = Truncation {
+            depth_limited_dirs: std::mem::take(&mut walker.depth_limited_dirs),
+            max_results_reached: false,
+            truncated_dirs: std::mem::take(&mut walker.truncated_dirs),
+            too_long_paths: std::mem::take(&mut walker.too_long_paths),
+            timed_out: walker.timed_out,
+        };
+        if !walker.timed_out
+            && walker
+                .option
+                .max_results
+                .is_some_and(|max| walker.yielded >= max)
+        {
+            truncation.max_results_reached =
+                iter::from_fn(|| walker.walk_next()).any(|res| res.is_ok());
+        }
+        truncation
+    }
+
+    /// the directories cut short by [`ListOption::max_entries_per_dir`] so far
+    pub fn truncated_dirs(&self) -> &[PathBuf] {
+        &self.walker.truncated_dirs
+    }
+
+    /// if the listing was stopped by [`ListOption::timeout`]
+    pub fn timed_out(&self) -> bool {
+        self.walker.timed_out
+    }
+
+    /// the paths skipped so far as they are too long for the system, e.g. beyond PATH_MAX
+    /// in a very deep tree, whatever the error policy, with the entries under them
+    pub fn too_long_paths(&self) -> &[PathBuf] {
+        &self.walker.too_long_paths
+    }
+}
+
+impl Iterator for TryListIter<'_> {
+    type Item = Result<PathBuf, ListError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.walker.next().map(|res| res.map(Entry::into_path))
+    }
+}
+
+// check if a directory on the device is on another one than the root, a device not known
+// on either side is taken as the same, so that nothing is skipped for want of it
+fn crosses(root: Option<u64>, device: Option<u64>) -> bool {
+    matches!((root, device), (Some(root), Some(device)) if root != device)
+}
+
+// the only place where the infallible apis give up on an error
+pub(crate) fn expect_listed<T>(res: Result<T, ListError>) -> T {
+    res.unwrap_or_else(|err| panic!("{}", err))
+}
+
+// the entries read between two checks of the time, with timeout
+const TIME_CHECK_ENTRIES: usize = 16;
+
+// the most directories kept open at once while going down a tree, the ones above are read
+// whole, so that a deep tree doesn't run out of file descriptors
+const MAX_OPEN_DIRS: usize = 64;
+
+// a directory being read
+struct DirState {
+    path: PathBuf,
+    read_dir: DirEntries,
+    // the depth of the entries in this directory
+    depth: usize,
+    // the level left for the entries in this directory, counting down from usize::MAX
+    // for the whole tree
+    level: usize,
+    // the ignore rules for the entries in this directory
+    ignores: Ignores,
+    // if true, this directory is excluded, so its entries are only listed if reincluded
+    excluded: bool,
+    // the entry of this directory, held until some entry under it is shown
+    // with prune_empty_dirs or dirs_with_matches_only, or until it is read with contents_first
+    held: Option<Entry>,
+    // if true, some entry under this directory is shown, with contents_first
+    matched: bool,
+    // the entries read from this directory, for max_entries_per_dir
+    read: usize,
+    // the names of the entries left when resumed from a checkpoint, the others being read
+    // before it, or created after it
+    left: Option<HashSet<OsString>>,
+}
+
+// the entries of a directory, as they are read or sorted by name or by kind
+enum DirEntries {
+    // boxed, as a ReadDir is large on windows
+    Read(Box<ReadDir>),
+    // read whole, to be sorted, to take a checkpoint or to close the directory,
+    // the errors come after the entries when sorted
+    Sorted(vec::IntoIter<io::Result<DirEntry>>),
+    // resumed from a checkpoint, read once it is on top of the stack again
+    Unread,
+}
+
+impl DirEntries {
+    fn new(read_dir: ReadDir, option: &ListOption) -> Self {
+        if !option.sorted && option.dirs_first.is_none() {
+            return DirEntries::Read(Box::new(read_dir));
+        }
+        // the group of each entry, so that the sort is stable within a group
+        let group = |entry: &io::Result<DirEntry>| match (entry, option.dirs_first) {
+            (Ok(entry), Some(dirs_first)) => {
+                let is_dir = match entry.file_type() {
+                    Ok(file_type) if file_type.is_symlink() && option.follow_symlinks => {
+                        entry.path().is_dir()
+                    }
+                    Ok(file_type) => file_type.is_dir(),
+                    Err(_) => false,
+                };
+                u8::from(is_dir != dirs_first)
+            }
+            (Ok(_), None) => 0,
+            (Err(_), _) => 2,
+        };
+        let mut entries: Vec<_> = read_dir.map(|entry| (group(&entry), entry)).collect();
+        entries.sort_by(|(a_group, a), (b_group, b)| {
+            a_group.cmp(b_group).then_with(|| match (a, b) {
+                (Ok(a), Ok(b)) if option.sorted => a
+                    .file_name()
+                    .as_encoded_bytes()
+                    .cmp(b.file_name().as_encoded_bytes()),
+                _ => Ordering::Equal,
+            })
+        });
+        let entries: Vec<_> = entries.into_iter().map(|(_, entry)| entry).collect();
+        DirEntries::Sorted(entries.into_iter())
+    }
+}
+
+impl Iterator for DirEntries {
+    type Item = io::Result<DirEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            DirEntries::Read(read_dir) => read_dir.next(),
+            DirEntries::Sorted(entries) => entries.next(),
+            DirEntries::Unread => None,
+        }
+    }
+}
+
+// a directory to be read on the next call, see DirState
+struct PendingDir {
+    path: PathBuf,
+    depth: usize,
+    level: usize,
+    // the ignore rules of its parent
+    ignores: Ignores,
+    excluded: bool,
+    held: Option<Entry>,
+}
+
+/// the traversal behind all listing apis, yielding the shown entries
+///
+/// it never recurses, the directories being read are kept on its own stack,
+/// so a deep tree can't overflow the call stack, while the paths beyond what the system
+/// allows are skipped and noted, see TryListIter::too_long_paths
+pub(crate) struct Walker<'a> {
+    option: &'a ListOption,
+    // set after an error is yielded under ErrorPolicy::Abort
+    aborted: bool,
+    // if true, skip the paths it may not read and a missing root whatever the policy,
+    // for the infallible apis
+    lenient: bool,
+    // the entries yielded so far, for max_results
+    yielded: usize,
+    // when the listing has to stop, with timeout
+    deadline: Option<Instant>,
+    // the entries read since the time was last checked
+    unchecked: usize,
+    // set once the deadline has passed
+    timed_out: bool,
+    // the path the listing starts from
+    root_path: PathBuf,
+    // the canonical form of the root, resolved once when some excluded path is absolute
+    abs_root: Option<PathBuf>,
+    // the root path not visited yet, taken on the first call of next
+    root: Option<PathBuf>,
+    // if true, the root was visited before the checkpoint the listing resumes from
+    resumed: bool,
+    // the last visited directory, read on the next call so that directories are read lazily
+    pending: Option<PendingDir>,
+    // the directories being read
+    stack: Vec<DirState>,
+    // the directories to read after the one being read, with breadth_first
+    queue: VecDeque<PendingDir>,
+    // the entries to yield before walking on
+    ready: VecDeque<Entry>,
+    // taken once when the listing starts, so that the time filters don't shift
+    start: ListStart,
+    // the name filters, folded once when the listing starts
+    names: NameFilters,
+    // the devices of the pseudo filesystems, with skip_pseudo_filesystems
+    pseudo_fs: PseudoFs,
+    // the device of the root, with same_file_system
+    root_device: Option<u64>,
+    // the directories not read whole, with max_entries_per_dir
+    truncated_dirs: Vec<PathBuf>,
+    // the paths skipped as too long for the system
+    too_long_paths: Vec<PathBuf>,
+    // if true, note the directories at the depth limit with some entry in them,
+    // for try_list_report
+    reports: bool,
+    // the directories noted so
+    depth_limited_dirs: Vec<PathBuf>,
+    // the ids of the files with several hardlinks listed, with dedup_hardlinks
+    hardlinks: HashSet<(u64, u64)>,
+    // the index of the repository of the root, read when the listing starts with git_tracked
+    #[cfg(feature = "git")]
+    git: Option<GitIndex>,
+}
+
+impl<'a> Walker<'a> {
+    pub(crate) fn new<P>(option: &'a ListOption, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        let root_path = path.as_ref().to_path_buf();
+        Self {
+            option,
+            aborted: false,
+            lenient: false,
+            yielded: 0,
+            deadline: option.timeout.map(|timeout| Instant::now() + timeout),
+            unchecked: 0,
+            timed_out: false,
+            root: Some(root_path.clone()),
+            resumed: false,
+            abs_root: option
+                .exclude_paths
+                .iter()
+                .any(|path| path.is_absolute())
+                .then(|| fs::canonicalize(&root_path).ok())
+                .flatten(),
+            root_path,
+            pending: None,
+            stack: Vec::new(),
+            queue: VecDeque::new(),
+            ready: VecDeque::new(),
+            start: option.start(),
+            names: option.name_filters(),
+            pseudo_fs: if option.skip_pseudo_fs {
+                PseudoFs::load()
+            } else {
+                PseudoFs::default()
+            },
+            root_device: None,
+            truncated_dirs: Vec::new(),
+            too_long_paths: Vec::new(),
+            reports: false,
+            depth_limited_dirs: Vec::new(),
+            hardlinks: HashSet::new(),
+            #[cfg(feature = "git")]
+            git: None,
+        }
+    }
+
+    // the walker of the infallible apis, see ListIter
+    pub(crate) fn infallible<P>(option: &'a ListOption, path: P) -> Self
+    where
+        P: AsRef<Path>,
+    {
+        Self {
+            lenient: true,
+            ..Self::new(option, path)
+        }
+    }
+
+    // go on from the checkpoint, the directories being read are read again when they are
+    // on top of the stack, only for the names left
+    fn resume(mut self, checkpoint: Checkpoint) -> Self {
+        let Some(started) = checkpoint.started else {
+            return self;
+        };
+        self.resumed = true;
+        self.start.now = started;
+        self.root_device = checkpoint.root_device;
+        self.truncated_dirs = checkpoint.truncated_dirs;
+        self.too_long_paths = checkpoint.too_long_paths;
+        self.hardlinks = checkpoint.hardlinks.into_iter().collect();
+        let ready = checkpoint
+            .ready
+            .iter()
+            .filter_map(|(path, depth)| self.reload(path, *depth))
+            .collect();
+        let stack = checkpoint
+            .stack
+            .into_iter()
+            .map(|dir| DirState {
+                ignores: self
+                    .ignores_above(&dir.path, dir.depth)
+                    .enter(self.option, &dir.path),
+                read_dir: DirEntries::Unread,
+                held: self.reload_held(&dir),
+                depth: dir.depth,
+                level: dir.level,
+                excluded: dir.excluded,
+                matched: dir.matched,
+                read: dir.read,
+                left: dir.left.map(|left| left.into_iter().collect()),
+                path: dir.path,
+            })
+            .collect();
+        let pending_dir = |dir: DirCheckpoint| PendingDir {
+            ignores: self.ignores_above(&dir.path, dir.depth),
+            held: self.reload_held(&dir),
+            depth: dir.depth,
+            level: dir.level,
+            excluded: dir.excluded,
+            path: dir.path,
+        };
+        let pending = checkpoint.pending.map(pending_dir);
+        let queue = checkpoint.queue.into_iter().map(pending_dir).collect();
+        self.ready = ready;
+        self.stack = stack;
+        self.pending = pending;
+        self.queue = queue;
+        self
+    }
+
+    // the state of the listing, see Checkpoint, reading the rest of the directories being read
+    // to note the names left in them, and going on with what was read
+    fn checkpoint(&mut self) -> Checkpoint {
+        let stack = self
+            .stack
+            .iter_mut()
+            .map(|dir| {
+                let left = if let DirEntries::Unread = dir.read_dir {
+                    dir.left.iter().flatten().cloned().collect()
+                } else {
+                    let rest: Vec<_> = dir.read_dir.by_ref().collect();
+                    let left = rest
+                        .iter()
+                        .filter_map(|entry| entry.as_ref().ok().map(DirEntry::file_name))
+                        .filter(|name| dir.left.as_ref().is_none_or(|left| left.contains(name)))
+                        .collect();
+                    dir.read_dir = DirEntries::Sorted(rest.into_iter());
+                    left
+                };
+                DirCheckpoint {
+                    path: dir.path.clone(),
+                    depth: dir.depth,
+                    level: dir.level,
+                    excluded: dir.excluded,
+                    held: dir.held.is_some(),
+                    matched: dir.matched,
+                    read: dir.read,
+                    left: Some(left),
+                }
+            })
+            .collect();
+        let pending_dir = |dir: &PendingDir| DirCheckpoint {
+            path: dir.path.clone(),
+            depth: dir.depth,
+            level: dir.level,
+            excluded: dir.excluded,
+            held: dir.held.is_some(),
+            matched: false,
+            read: 0,
+            left: None,
+        };
+        Checkpoint {
+            started: (self.root.is_none() || self.resumed).then_some(self.start.now),
+            stack,
+            pending: self.pending.as_ref().map(pending_dir),
+            queue: self.queue.iter().map(pending_dir).collect(),
+            ready: self
+                .ready
+                .iter()
+                .map(|entry| (entry.path.clone(), entry.depth))
+                .collect(),
+            root_device: self.root_device,
+            truncated_dirs: self.truncated_dirs.clone(),
+            too_long_paths: self.too_long_paths.clone(),
+            hardlinks: self.hardlinks.iter().copied().collect(),
+        }
+    }
+
+    // the entry of a path shown before the checkpoint, None if it is removed since
+    fn reload(&self, path: &Path, depth: usize) -> Option<Entry> {
+        let file_type = fs::symlink_metadata(path).ok()?.file_type();
+        let metadata = if !file_type.is_symlink() || self.option.follow_symlinks || depth == 0 {
+            // a broken symlink shown before has only the metadata of its own
+            fs::metadata(path).or_else(|_| fs::symlink_metadata(path))
+        } else {
+            fs::symlink_metadata(path)
+        };
+        Some(Entry {
+            path: path.to_path_buf(),
+            file_type,
+            depth,
+            metadata: metadata.ok()?,
+        })
+    }
+
+    // the entry of a directory held before the checkpoint, see DirState
+    fn reload_held(&self, dir: &DirCheckpoint) -> Option<Entry> {
+        dir.held
+            .then(|| self.reload(&dir.path, dir.depth - 1))
+            .flatten()
+    }
+
+    // the ignore rules of the parent of the directory whose entries are at the depth,
+    // loaded again from the root down
+    fn ignores_above(&self, dir: &Path, depth: usize) -> Ignores {
+        let above: Vec<_> = dir.ancestors().skip(1).take(depth - 1).collect();
+        above.iter().rev().fold(Ignores::root(), |ignores, dir| {
+            ignores.enter(self.option, dir)
+        })
+    }
+}
+
+impl Iterator for Walker<'_> {
+    type Item = Result<Entry, ListError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.aborted
+            || self.timed_out
+            || self.option.is_interrupted()
+            || self
+                .option
+                .max_results
+                .is_some_and(|max| self.yielded >= max)
+        {
+            return None;
+        }
+        loop {
+            match self.walk_next()? {
+                Ok(entry) => {
+                    self.yielded += 1;
+                    return Some(Ok(entry));
+                }
+                Err(err) if self.skips(&err) => continue,
+                Err(err) => match self.option.error_policy {
+                    ErrorPolicy::Skip => continue,
+                    ErrorPolicy::Abort => {
+                        self.aborted = true;
+                        return Some(Err(err));
+                    }
+                    ErrorPolicy::Collect => return Some(Err(err)),
+                },
+            }
+        }
+    }
+}
+
+impl Walker<'_> {
+    // check if the infallible apis skip the error whatever the policy: a path it may not read,
+    // a missing root, an entry whose metadata can't be fetched, or a root outside a repository
+    fn skips(&self, err: &ListError) -> bool {
+        #[cfg(feature = "git")]
+        let is_git = matches!(err, ListError::Git { .. });
+        #[cfg(not(feature = "git"))]
+        let is_git = false;
+        self.lenient
+            && (err.is_permission_denied()
+                || is_git
+                || matches!(err, ListError::NotFound { .. } | ListError::Metadata { .. }))
+    }
+
+    // walk to the next shown entry or error regardless of the error policy
+    fn walk_next(&mut self) -> Option<Result<Entry, ListError>> {
+        if let Some(entry) = self.ready.pop_front() {
+            return Some(Ok(entry));
+        }
+        if let Some(root) = self.root.take() {
+            let level = self.option.depth_limit().unwrap_or(usize::MAX);
+            if level == 0 {
+                return None;
+            }
+            let file_type = match fs::symlink_metadata(&root) {
+                Ok(metadata) => metadata.file_type(),
+                Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                    return Some(Err(ListError::NotFound { path: root }))
+                }
+                Err(source) => return Some(Err(ListError::Metadata { path: root, source })),
+            };
+            // a broken symlink as the root points to nothing to list, unless it is listed itself
+            if file_type.is_symlink()
+                && !self.option.broken_symlinks
+                && fs::metadata(&root).is_err_and(|err| is_dangling(&err))
+            {
+                return Some(Err(ListError::NotFound { path: root }));
+            }
+            // nothing is listed when the repository can't be read, whatever the error policy
+            #[cfg(feature = "git")]
+            if self.option.git_tracked.is_some() {
+                match GitIndex::open(&root) {
+                    Ok(git) => self.git = Some(git),
+                    Err(source) => return Some(Err(ListError::Git { path: root, source })),
+                }
+            }
+            if !self.resumed {
+                match self.visit(root, file_type, 0, level, Ignores::root(), false) {
+                    Ok(Some(entry)) => {
+                        if let Some(entry) = self.emit(entry) {
+                            return Some(Ok(entry));
+                        }
+                    }
+                    Ok(None) => {}
+                    Err(err) => return Some(Err(err)),
+                }
+            }
+        }
+        loop {
+            if self.option.is_interrupted() {
+                return None;
+            }
+            if self.stack.is_empty() && self.pending.is_none() {
+                self.pending = self.queue.pop_front();
+            }
+            if self.is_past_deadline() {
+                self.timed_out = true;
+                return None;
+            }
+            if let Some(dir) = self.pending.take() {
+                match dir.path.read_dir() {
+                    // removed since it was visited
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                    Err(err) if err.kind() == io::ErrorKind::InvalidFilename => {
+                        self.too_long_paths.push(dir.path);
+                        continue;
+                    }
+                    Ok(read_dir) => {
+                        self.close_oldest_dir();
+                        self.stack.push(DirState {
+                            ignores: dir.ignores.enter(self.option, &dir.path),
+                            path: dir.path,
+                            read_dir: DirEntries::new(read_dir, self.option),
+                            depth: dir.depth,
+                            level: dir.level,
+                            excluded: dir.excluded,
+                            held: dir.held,
+                            matched: false,
+                            read: 0,
+                            left: None,
+                        })
+                    }
+                    Err(source) => {
+                        // the directory itself is still listed after the error
+                        if let Some(entry) = dir.held.filter(|_| self.lists_unmatched_dirs()) {
+                            self.ready.push_back(entry);
+                        }
+                        return Some(Err(ListError::ReadDir {
+                            path: dir.path,
+                            source,
+                        }));
+                    }
+                }
+            }
+            let dir = self.stack.last_mut()?;
+            if let DirEntries::Unread = dir.read_dir {
+                match dir.path.read_dir() {
+                    Ok(read_dir) => dir.read_dir = DirEntries::new(read_dir, self.option),
+                    Err(source) => {
+                        // ended as an empty directory
+                        dir.read_dir = DirEntries::Sorted(Vec::new().into_iter());
+                        if source.kind() != io::ErrorKind::NotFound {
+                            return Some(Err(ListError::ReadDir {
+                                path: dir.path.clone(),
+                                source,
+                            }));
+                        }
+                    }
+                }
+            }
+            let capped = self
+                .option
+                .max_entries_per_dir
+                .is_some_and(|max| dir.read >= max);
+            let mut next = dir.read_dir.next();
+            // only the entries left at the checkpoint are visited
+            if let Some(left) = &dir.left {
+                while let Some(Ok(entry)) = &next {
+                    if left.contains(&entry.file_name()) {
+                        break;
+                    }
+                    next = dir.read_dir.next();
+                }
+            }
+            // the rest of a directory beyond the cap is not read, only noted
+            if capped && next.is_some() {
+                self.truncated_dirs.push(dir.path.clone());
+                next = None;
+            }
+            let entry = match next {
+                Some(Ok(entry)) => {
+                    dir.read += 1;
+                    entry
+                }
+                Some(Err(source)) => {
+                    return Some(Err(ListError::ReadDir {
+                        path: dir.path.clone(),
+                        source,
+                    }))
+                }
+                None => {
+                    let dir = self.stack.pop()?;
+                    if let Some(entry) = dir
+                        .held
+                        .filter(|_| dir.matched || self.lists_unmatched_dirs())
+                    {
+                        self.ready.push_back(entry);
+                        return self.ready.pop_front().map(Ok);
+                    }
+                    continue;
+                }
+            };
+            let depth = dir.depth;
+            let ignores = dir.ignores.clone();
+            let excluded = dir.excluded;
+            let sub_level = dir.level - 1;
+            let path = entry.path();
+            let file_type = match entry.file_type() {
+                Ok(file_type) => file_type,
+                Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                Err(source) => return Some(Err(ListError::Metadata { path, source })),
+            };
+            match self.visit(path, file_type, depth, sub_level, ignores, excluded) {
+                Ok(Some(entry)) => {
+                    if let Some(entry) = self.emit(entry) {
+                        return Some(Ok(entry));
+                    }
+                }
+                Ok(None) => {}
+                // removed while it was checked
+                Err(err) if err.is_not_found() => {}
+                Err(err) => return Some(Err(err)),
+            }
+        }
+    }
+
+    // hold the entry of the directory to be read next with prune_empty_dirs or contents_first,
+    // drop any other directory with dirs_with_matches_only,
+    // otherwise queue it after the held directories above it, and take the first entry queued,
+    // while with contents_first, the held directories are queued when they are read
+    fn emit(&mut self, entry: Entry) -> Option<Entry> {
+        let contents_first = self.contents_first();
+        if contents_first || self.option.prune_empty_dirs || self.option.dirs_with_matches_only {
+            if let Some(dir) = self.pending.as_mut().filter(|dir| dir.path == entry.path) {
+                dir.held = Some(entry);
+                return None;
+            }
+        }
+        if self.option.dirs_with_matches_only
+            && !self.option.breadth_first
+            && entry.metadata.is_dir()
+        {
+            return None;
+        }
+        if contents_first {
+            self.stack.iter_mut().for_each(|dir| dir.matched = true);
+        } else {
+            self.ready
+                .extend(self.stack.iter_mut().filter_map(|dir| dir.held.take()));
+        }
+        self.ready.push_back(entry);
+        self.ready.pop_front()
+    }
+
+    // check if the directories come after the entries under them
+    fn contents_first(&self) -> bool {
+        self.option.contents_first && !self.option.breadth_first
+    }
+
+    // read whole the lowest directory still open when there are MAX_OPEN_DIRS of them,
+    // only up to the entry past max_entries_per_dir, so that it is still seen as cut
+    fn close_oldest_dir(&mut self) {
+        let is_open = |dir: &&mut DirState| matches!(dir.read_dir, DirEntries::Read(_));
+        if self.stack.iter_mut().filter(is_open).count() < MAX_OPEN_DIRS {
+            return;
+        }
+        let max = self.option.max_entries_per_dir;
+        if let Some(dir) = self.stack.iter_mut().find(is_open) {
+            let left = max.map_or(usize::MAX, |max| max.saturating_sub(dir.read) + 1);
+            let rest: Vec<_> = dir.read_dir.by_ref().take(left).collect();
+            dir.read_dir = DirEntries::Sorted(rest.into_iter());
+        }
+    }
+
+    // check if a directory held with contents_first is listed even if nothing under it is
+    fn lists_unmatched_dirs(&self) -> bool {
+        self.contents_first()
+            && !self.option.prune_empty_dirs
+            && !self.option.dirs_with_matches_only
+    }
+
+    // check the time before each directory read, and every TIME_CHECK_ENTRIES entries read
+    // in between, so that slow filters are cut too without reading the clock for each entry
+    fn is_past_deadline(&mut self) -> bool {
+        let Some(deadline) = self.deadline else {
+            return false;
+        };
+        if self.pending.is_none() {
+            if self.stack.is_empty() {
+                return false;
+            }
+            self.unchecked += 1;
+            if self.unchecked < TIME_CHECK_ENTRIES {
+                return false;
+            }
+        }
+        self.unchecked = 0;
+        Instant::now() >= deadline
+    }
+
+    // take the device of the root when it is visited, with same_file_system
+    fn note_root_device(&mut self, path: &Path, metadata: &fs::Metadata) {
+        if self.option.same_file_system {
+            self.root_device = file_id(path, metadata).map(|(device, _)| device);
+        }
+    }
+
+    // check if the directory is on another device than the root with same_file_system
+    fn crosses_device(&self, path: &Path, metadata: &fs::Metadata) -> bool {
+        self.option.same_file_system
+            && crosses(
+                self.root_device,
+                file_id(path, metadata).map(|(device, _)| device),
+            )
+    }
+
+    // check if the directory at this depth is one of the directories above it,
+    // i.e. a symlink to it would loop forever
+    fn is_ancestor(&self, path: &Path, depth: usize, metadata: &fs::Metadata) -> bool {
+        let Some(id) = file_id(path, metadata) else {
+            return false;
+        };
+        // the path is the root joined with the names under it, so its parents are the ancestors
+        path.ancestors()
+            .skip(1)
+            .take(depth)
+            .any(|dir| fs::metadata(dir).is_ok_and(|metadata| file_id(dir, &metadata) == Some(id)))
+    }
+
+    // check if the path is shown, and mark it to be read next if it is a directory to descend into,
+    // sub_level is the level left for its entries, ignores are the ignore rules of its parent,
+    // and excluded is true if its parent is excluded
+    fn visit(
+        &mut self,
+        path: PathBuf,
+        file_type: FileType,
+        depth: usize,
+        sub_level: usize,
+        ignores: Ignores,
+        excluded: bool,
+    ) -> Result<Option<Entry>, ListError> {
+        let follow = !file_type.is_symlink() || self.option.follow_symlinks || depth == 0;
+        let metadata = if follow {
+            fs::metadata(&path)
+        } else {
+            fs::symlink_metadata(&path)
+        };
+        let metadata = match metadata {
+            Ok(metadata) => metadata,
+            // a broken symlink is never descended into, and only shown if asked
+            Err(err) if is_dangling(&err) => {
+                if !(self.option.broken_symlinks && file_type.is_symlink()) {
+                    return Ok(None);
+                }
+                fs::symlink_metadata(&path).map_err(|source| ListError::Metadata {
+                    path: path.clone(),
+                    source,
+                })?
+            }
+            // too deep for the system to tell, so neither it nor what is under it is listed
+            Err(err) if err.kind() == io::ErrorKind::InvalidFilename => {
+                self.too_long_paths.push(path);
+                return Ok(None);
+            }
+            Err(source) => return Err(ListError::Metadata { path, source }),
+        };
+        if depth == 0 {
+            self.note_root_device(&path, &metadata);
+        }
+        let mut candidate = Candidate {
+            path: &path,
+            metadata: &metadata,
+            rel_path: path
+                .strip_prefix(&self.root_path)
+                .ok()
+                .filter(|_| depth > 0),
+            abs_root: self.abs_root.as_deref(),
+            excluded,
+            is_symlink: file_type.is_symlink(),
+            start: self.start,
+            names: &self.names,
+        };
+        candidate.excluded |= depth > 0 && ignores.is_ignored(&path, metadata.is_dir());
+        candidate.excluded |= depth > 0 && self.option.prunes(&candidate);
+        let excluded = candidate.excluded;
+        // an excluded subtree is skipped, unless something in it may be reincluded
+        if excluded && !self.option.has_reincludes() {
+            return Ok(None);
+        }
+        #[cfg(feature = "git")]
+        let git_status = self.git.as_ref().map(|git| git.status(candidate.rel_path));
+        #[cfg(feature = "git")]
+        if git_status == Some(GitStatus::GitDir) {
+            return Ok(None);
+        }
+        #[cfg(feature = "git")]
+        let is_boundary = git_status == Some(GitStatus::Submodule);
+        #[cfg(not(feature = "git"))]
+        let is_boundary = false;
+        let descends = metadata.is_dir()
+            && (sub_level > 0 || self.reports)
+            && !is_boundary
+            && !(candidate.is_symlink && self.is_ancestor(&path, depth, &metadata))
+            && !self.crosses_device(&path, &metadata)
+            && !(depth > 0 && self.pseudo_fs.contains(&metadata))
+            && !(depth > 0 && self.option.is_bundle(&path, &metadata))
+            && (!excluded || self.option.may_reinclude_under(candidate.rel_path));
+        if descends && sub_level > 0 {
+            let dir = PendingDir {
+                path: path.clone(),
+                depth: depth + 1,
+                level: sub_level,
+                ignores,
+                excluded,
+                held: None,
+            };
+            if self.option.breadth_first {
+                self.queue.push_back(dir);
+            } else {
+                self.pending = Some(dir);
+            }
+        } else if descends
+            && fs::read_dir(&path).is_ok_and(|mut read_dir| read_dir.next().is_some())
+        {
+            self.depth_limited_dirs.push(path.clone());
+        }
+        if (depth == 0 && !self.option.include_root) || depth < self.option.min_depth {
+            return Ok(None);
+        }
+        #[cfg(feature = "git")]
+        if let (Some(status), Some(if_tracked)) = (git_status, self.option.git_tracked) {
+            if !status.shows(if_tracked) {
+                return Ok(None);
+            }
+        }
+        if !self.option.show_with(&candidate)? {
+            return Ok(None);
+        }
+        if self.option.dedup_hardlinks {
+            if let Some(id) = hardlink_id(&path, &metadata) {
+                if !self.hardlinks.insert(id) {
+                    return Ok(None);
+                }
+            }
+        }
+        Ok(Some(Entry {
+            path,
+            file_type,
+            depth,
+            metadata,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{crosses, MAX_OPEN_DIRS};
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn crosses_only_between_known_devices() {
+        assert!(!crosses(Some(1), Some(1)));
+        assert!(crosses(Some(1), Some(2)));
+        assert!(!crosses(None, Some(2)));
+        assert!(!crosses(Some(1), None));
+        assert!(!crosses(None, None));
+    }
+
+    #[test]
+    fn same_file_system_lists_a_single_device_tree() {
+        let dir = TestDir::new();
+        dir.file("sub/a.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        let all = option.list_paths(dir.path());
+        assert_eq!(option.same_file_system(true).list_paths(dir.path()), all);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn self_loop_symlink_is_skipped_like_a_broken_one() {
+        let dir = TestDir::new();
+        dir.file("a.txt", b"");
+        dir.symlink("loop", "loop");
+        let mut option = ListOption::default();
+        option.include_root(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a.txt"]);
+        assert_eq!(dir.rel(option.try_list(dir.path()).unwrap()), ["a.txt"]);
+        option.include_broken_symlinks(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a.txt", "loop"]);
+        // a symlink is never a junction
+        assert!(option
+            .entries(dir.path())
+            .iter()
+            .all(|entry| !entry.is_junction()));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_to_ancestors_are_listed_not_descended() {
+        let dir = TestDir::new();
+        dir.file("a/b/c.txt", b"");
+        dir.symlink("..", "a/b/up");
+        dir.symlink("../..", "a/b/top");
+        dir.symlink("a", "alias");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        let listed = dir.rel(option.list_paths(dir.path()));
+        assert!(listed.contains(&"a/b/up".to_string()));
+        assert!(listed.contains(&"a/b/top".to_string()));
+        assert!(!listed.iter().any(|path| path.starts_with("a/b/up/")));
+        assert!(!listed.iter().any(|path| path.starts_with("a/b/top/")));
+        // a symlink to a sibling tree is not a loop, and is listed through
+        assert!(listed.contains(&"alias/b/c.txt".to_string()));
+        assert_eq!(option.try_list(dir.path()).unwrap().len(), listed.len());
+    }
+
+    #[test]
+    fn root_entry_has_depth_zero_and_its_kind() {
+        let dir = TestDir::new();
+        dir.file("sub/a.txt", b"");
+        let entries = ListOption::default().recursive(true).entries(dir.path());
+        assert_eq!(entries[0].path(), dir.path());
+        assert_eq!(entries[0].depth(), 0);
+        assert_eq!(entries[0].kind(), crate::EntryKind::Dir);
+        let file = entries.iter().find(|entry| entry.path().ends_with("a.txt"));
+        assert_eq!(file.map(|entry| entry.depth()), Some(2));
+        let paths = entries.into_iter().map(|entry| entry.into_path()).collect();
+        assert_eq!(dir.rel(paths), ["", "sub", "sub/a.txt"]);
+    }
+
+    #[test]
+    fn dirs_deeper_than_the_open_ones_are_listed_whole() {
+        let dir = TestDir::new();
+        let depth = MAX_OPEN_DIRS * 3;
+        for level in 1..=depth {
+            let path = "d/".repeat(level);
+            for name in ["1", "2", "3"] {
+                dir.file(&(path.clone() + name), b"");
+            }
+        }
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        assert_eq!(option.count(dir.path()), depth * 4);
+        // each directory but the last has 4 entries, whether it is read whole or not
+        for (max, truncated) in [(4, 0), (3, depth - 1)] {
+            option.max_entries_per_dir(max);
+            let mut iter = option.try_iter(dir.path());
+            assert!(iter.by_ref().all(|res| res.is_ok()));
+            assert_eq!(iter.truncated_dirs().len(), truncated, "{}", max);
+        }
+    }
+
+    // the other tests open files at the same time, so the bound checked is loose,
+    // but a walk holding every directory open would pass it by far
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn open_dirs_are_bounded() {
+        let open = || std::fs::read_dir("/proc/self/fd").unwrap().count();
+        let dir = TestDir::new();
+        let depth = MAX_OPEN_DIRS * 16;
+        dir.chain(depth);
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        let before = open();
+        let mut most = before;
+        let mut listed = 0;
+        for path in option.iter(dir.path()) {
+            assert!(path.ends_with("d"));
+            listed += 1;
+            if listed % 16 == 0 {
+                most = most.max(open());
+            }
+        }
+        assert_eq!(listed, depth);
+        assert!(most - before < MAX_OPEN_DIRS * 4, "{}", most - before);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn deep_chain_is_listed_without_crashing() {
+        let dir = TestDir::new();
+        dir.chain(5000);
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        let listed = option.list_paths(dir.path());
+        assert!(!listed.is_empty());
+        assert!(listed.windows(2).all(|pair| pair[1].starts_with(&pair[0])));
+        let mut iter = option.try_iter(dir.path());
+        assert!(iter.by_ref().all(|res| res.is_ok()));
+        // the rest of the chain is beyond PATH_MAX on most systems
+        if listed.len() < 5000 {
+            assert_eq!(iter.too_long_paths().len(), 1);
+            assert!(option.try_list(dir.path()).is_ok());
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn junction_cycle_ends() {
+        let dir = TestDir::new();
+        dir.file("sub/a.txt", b"");
+        let junction = dir.join("sub/loop");
+        let status = std::process::Command::new("cmd")
+            .arg("/C")
+            .arg("mklink")
+            .arg("/J")
+            .arg(&junction)
+            .arg(dir.path())
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        let entries = option.entries(dir.path());
+        let paths = entries
+            .iter()
+            .map(|entry| entry.path().to_path_buf())
+            .collect();
+        assert_eq!(dir.rel(paths), ["sub", "sub/a.txt", "sub/loop"]);
+        let junction = entries.iter().find(|entry| entry.path().ends_with("loop"));
+        assert!(junction.is_some_and(|entry| entry.is_junction()));
+        assert!(!entries[0].is_junction());
+        assert_eq!(
+            option.follow_symlinks(false).list_paths(dir.path()).len(),
+            3
+        );
+    }
+}
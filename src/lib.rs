@@ -1,3 +1,39 @@
+mod checkpoint;
+mod content;
+mod entry;
+mod error;
+#[cfg(feature = "git")]
+mod git;
+#[cfg(feature = "glob")]
+mod glob;
+mod ignore_file;
+mod interrupt;
+mod iter;
+mod metadata;
+mod mounts;
+mod name;
 mod option;
+mod path_len;
+mod precedence;
+mod predicate;
+mod prune;
+#[cfg(feature = "regex")]
+mod regex_filter;
+mod report;
+#[cfg(test)]
+mod test_dir;
+#[cfg(all(unix, feature = "xattr"))]
+mod xattr_filter;
 
+pub use checkpoint::Checkpoint;
+pub use entry::*;
+pub use error::*;
+#[cfg(feature = "git")]
+pub use git::GitError;
+#[cfg(feature = "glob")]
+pub use glob::*;
+pub use iter::*;
 pub use option::*;
+#[cfg(feature = "regex")]
+pub use regex_filter::*;
+pub use report::Truncation;
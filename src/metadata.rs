@@ -0,0 +1,1261 @@
+use std::{
+    fs::{self, Metadata},
+    io,
+    path::Path,
+    time::{Duration, SystemTime},
+};
+
+#[cfg(not(unix))]
+use crate::name::has_extension;
+use crate::{name::push_unique, option::normalize_ext, Candidate, ListError, ListOption};
+
+/// filter entries by their metadata
+impl ListOption {
+    /// list only files of at least this size in bytes, directories are not filtered
+    ///
+    /// the size is the one of `Metadata::len`, and a file of exactly this size is listed,
+    /// e.g. min_size(100 * 1024 * 1024) lists the files of 100 MiB and more
+    pub fn min_size(&mut self, size: u64) -> &mut Self {
+        self.min_size = Some(size);
+        self
+    }
+
+    /// list only files of at most this size in bytes, directories are not filtered
+    ///
+    /// a file of exactly this size is listed, e.g. max_size(0) lists the empty files
+    pub fn max_size(&mut self, size: u64) -> &mut Self {
+        self.max_size = Some(size);
+        self
+    }
+
+    /// set if only the empty files will be listed, default false
+    ///
+    /// a file is empty when it is a regular file whose `Metadata::len` is 0,
+    /// directories are not filtered, and a symlink is never empty, whatever it points to,
+    /// so it is dropped by this and kept by [`ListOption::skip_empty_files`],
+    /// e.g. only_empty_files(true).only_file().ext("log").recursive(true) finds the empty logs
+    pub fn only_empty_files(&mut self, if_only: bool) -> &mut Self {
+        self.only_empty = if_only;
+        self
+    }
+
+    /// set if the empty files will be skipped, default false, see [`ListOption::only_empty_files`]
+    pub fn skip_empty_files(&mut self, if_skip: bool) -> &mut Self {
+        self.skip_empty = if_skip;
+        self
+    }
+
+    /// list only the directories without any entry, hidden ones included,
+    /// e.g. only_empty_dirs().recursive(true) finds the directories to delete
+    pub fn only_empty_dirs(&mut self) -> &mut Self {
+        self.file = false;
+        self.dir = true;
+        self.only_empty_dirs = true;
+        self
+    }
+
+    /// set if a directory is dropped from the listing when no entry under it is listed,
+    /// default false
+    ///
+    /// e.g. ext("rs").recursive(true).prune_empty_dirs(true) lists the directories
+    /// with some .rs file under them, along with the files, but no empty or unrelated directory
+    ///
+    /// only the directories read by the listing are dropped, so a directory beyond the level
+    /// is still listed, and a directory is yielded just before the first entry under it is,
+    /// which means the entries of a large directory are read before it is yielded
+    pub fn prune_empty_dirs(&mut self, if_prune: bool) -> &mut Self {
+        self.prune_empty_dirs = if_prune;
+        self
+    }
+
+    /// set if a directory is listed only when some non-directory entry under it is listed,
+    /// at any depth within the level, default false
+    ///
+    /// e.g. ext("rs").dir(true).recursive(true).dirs_with_matches_only(true)
+    /// lists the tree of the .rs files, where a chain of directories without any of them
+    /// disappears entirely, unlike with [`ListOption::prune_empty_dirs`],
+    /// and a directory beyond the level, which isn't read, is never listed
+    ///
+    /// a directory is yielded just before the first entry under it is, as with prune_empty_dirs
+    pub fn dirs_with_matches_only(&mut self, if_only: bool) -> &mut Self {
+        self.dirs_with_matches_only = if_only;
+        self
+    }
+
+    /// list only entries modified at this time or later
+    ///
+    /// with [`ListOption::modified_before`] too, only entries modified in between are listed,
+    /// the bounds included
+    ///
+    /// directories are filtered too, but still descended into, so recursive(true) finds
+    /// the new files under a directory not modified for long
+    ///
+    /// when the modification time isn't available on the platform,
+    /// the entry fails with `ListError::Metadata`, handled by the error policy
+    pub fn modified_after(&mut self, time: SystemTime) -> &mut Self {
+        self.modified_after = Some(time);
+        self
+    }
+
+    /// list only entries modified at this time or earlier, see [`ListOption::modified_after`]
+    pub fn modified_before(&mut self, time: SystemTime) -> &mut Self {
+        self.modified_before = Some(time);
+        self
+    }
+
+    /// list only entries modified at least this long ago,
+    /// e.g. ext("log").older_than(Duration::from_secs(30 * 24 * 60 * 60)) lists the old logs
+    ///
+    /// the age is counted from the time the listing started, taken once,
+    /// so a long listing filters all the entries against the same cutoff,
+    /// otherwise it works like [`ListOption::modified_before`]
+    pub fn older_than(&mut self, age: Duration) -> &mut Self {
+        self.older_than = Some(age);
+        self
+    }
+
+    /// list only entries modified at most this long ago, see [`ListOption::older_than`]
+    pub fn newer_than(&mut self, age: Duration) -> &mut Self {
+        self.newer_than = Some(age);
+        self
+    }
+
+    /// list only entries modified strictly after the file at this path, like make does,
+    /// e.g. ext("rs").newer_than_path("target/libfoo.rlib") lists the sources changed since
+    ///
+    /// the reference is read once when a listing starts, and a relative path is relative to
+    /// the current directory, not to the listing root
+    ///
+    /// when the reference doesn't exist, nothing is filtered,
+    /// as a missing output is older than all its sources
+    pub fn newer_than_path<P>(&mut self, path: P) -> &mut Self
+    where
+        P: AsRef<Path>,
+    {
+        self.newer_than_path = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// list only entries with some of the permission bits of the mask, only on unix,
+    /// e.g. mode_any(0o022) lists what is group or world writable
+    ///
+    /// the mode is the one of `MetadataExt::mode` masked with 0o7777,
+    /// so the setuid, setgid and sticky bits count, but not the file type
+    #[cfg(unix)]
+    pub fn mode_any(&mut self, mask: u32) -> &mut Self {
+        self.mode_any = Some(mask);
+        self
+    }
+
+    /// list only entries with all the permission bits of the mask, only on unix,
+    /// e.g. mode_all(0o111) lists what everyone can execute, see [`ListOption::mode_any`]
+    #[cfg(unix)]
+    pub fn mode_all(&mut self, mask: u32) -> &mut Self {
+        self.mode_all = Some(mask);
+        self
+    }
+
+    /// list only entries with exactly these permission bits, only on unix,
+    /// e.g. mode_exact(0o644), see [`ListOption::mode_any`]
+    #[cfg(unix)]
+    pub fn mode_exact(&mut self, mode: u32) -> &mut Self {
+        self.mode_exact = Some(mode);
+        self
+    }
+
+    /// list only entries owned by the user of this id, only on unix
+    #[cfg(unix)]
+    pub fn owned_by_uid(&mut self, uid: u32) -> &mut Self {
+        self.uid = Some(uid);
+        self
+    }
+
+    /// list only entries owned by the group of this id, only on unix
+    #[cfg(unix)]
+    pub fn owned_by_gid(&mut self, gid: u32) -> &mut Self {
+        self.gid = Some(gid);
+        self
+    }
+
+    /// set if only entries owned by another user than the current one will be listed,
+    /// default false, only on unix
+    ///
+    /// the current user is the real user of the process, taken once when a listing starts
+    #[cfg(unix)]
+    pub fn not_owned_by_current_user(&mut self, if_only: bool) -> &mut Self {
+        self.not_owned_by_me = if_only;
+        self
+    }
+
+    /// list only files with at least this many hardlinks, only on unix,
+    /// e.g. min_links(2) lists the files also linked elsewhere, which are risky to edit in place
+    ///
+    /// the count is the one of `MetadataExt::nlink`, and directories are not filtered
+    #[cfg(unix)]
+    pub fn min_links(&mut self, links: u64) -> &mut Self {
+        self.min_links = Some(links);
+        self
+    }
+
+    /// list only files with at most this many hardlinks, only on unix,
+    /// e.g. max_links(1) lists the files linked only once, see [`ListOption::min_links`]
+    #[cfg(unix)]
+    pub fn max_links(&mut self, links: u64) -> &mut Self {
+        self.max_links = Some(links);
+        self
+    }
+
+    /// set if only the sparse files will be listed, default false, only on unix
+    ///
+    /// a file is sparse when it allocates fewer bytes than its size, by `MetadataExt::blocks`
+    /// of 512 bytes, by at least the threshold, see [`ListOption::sparse_threshold`],
+    /// and directories are not filtered
+    ///
+    /// some file systems don't support sparse files, so nothing is sparse there
+    #[cfg(unix)]
+    pub fn only_sparse(&mut self, if_only: bool) -> &mut Self {
+        self.only_sparse = if_only;
+        self
+    }
+
+    /// set the bytes a file must allocate less than its size to be sparse, default 4096,
+    /// which is one block on most file systems, see [`ListOption::only_sparse`]
+    #[cfg(unix)]
+    pub fn sparse_threshold(&mut self, bytes: u64) -> &mut Self {
+        self.sparse_threshold = bytes;
+        self
+    }
+
+    /// set if only the executable files will be listed, default false,
+    /// like `find -type f -perm /111`
+    ///
+    /// on unix, a file is executable when it has any execute bit, and a directory never is,
+    /// elsewhere a file is executable by its ext, see [`ListOption::executable_exts`]
+    pub fn only_executable(&mut self, if_only: bool) -> &mut Self {
+        self.only_executable = if_only;
+        self
+    }
+
+    /// replace the exts making a file executable where there is no execute bit,
+    /// default exe, bat, cmd, com and ps1, matched ignoring the ascii case
+    ///
+    /// it has no effect on unix, where the execute bits are checked instead
+    pub fn executable_exts<I, S>(&mut self, exts: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.executable_exts.clear();
+        for ext in exts {
+            push_unique(&mut self.executable_exts, normalize_ext(ext.as_ref()));
+        }
+        self
+    }
+
+    /// set if only entries the process can read will be listed, default false
+    ///
+    /// on unix, it asks `faccessat` with the effective ids of the process,
+    /// so root can read everything, elsewhere everything listed is readable,
+    /// as its metadata could be read
+    ///
+    /// nothing is opened nor modified, so an entry may still fail to open later,
+    /// e.g. when it changes in between
+    pub fn readable(&mut self, if_only: bool) -> &mut Self {
+        self.readable = if_only;
+        self
+    }
+
+    /// set if only entries the process can write will be listed, default false
+    ///
+    /// on unix, it asks `faccessat` like [`ListOption::readable`],
+    /// elsewhere an entry is writable unless it is read-only
+    pub fn writable(&mut self, if_only: bool) -> &mut Self {
+        self.writable = if_only;
+        self
+    }
+
+    /// set if only the read-only files will be listed, default false
+    ///
+    /// a file is read-only by `Permissions::readonly`, which is the read-only attribute
+    /// on windows and no write bit at all on unix, whoever the owner is,
+    /// and a symlink is checked by the file it points to
+    ///
+    /// directories are not filtered, unless [`ListOption::apply_readonly_to_dirs`] is set
+    pub fn only_readonly(&mut self, if_only: bool) -> &mut Self {
+        self.only_readonly = if_only;
+        self
+    }
+
+    /// set if the read-only files will be skipped, default false,
+    /// see [`ListOption::only_readonly`]
+    pub fn skip_readonly(&mut self, if_skip: bool) -> &mut Self {
+        self.skip_readonly = if_skip;
+        self
+    }
+
+    /// set if only_readonly and skip_readonly also apply to directories, default false
+    pub fn apply_readonly_to_dirs(&mut self, if_apply: bool) -> &mut Self {
+        self.readonly_on_dirs = if_apply;
+        self
+    }
+
+    /// set if an entry with the hidden attribute counts as hidden, default true
+    ///
+    /// on windows, an entry is hidden when its name starts with `.`
+    /// or when it has FILE_ATTRIBUTE_HIDDEN, unless this is false,
+    /// while elsewhere this does nothing, see [`ListOption::ignore_finder_flags`] for macos
+    ///
+    /// [`ListOption::would_show_name`] never sees the attributes
+    pub fn hidden_by_attribute(&mut self, if_hidden: bool) -> &mut Self {
+        self.hidden_by_attribute = if_hidden;
+        self
+    }
+
+    /// set if the UF_HIDDEN flag is ignored, default false
+    ///
+    /// on macos, an entry is hidden when its name starts with `.` or when it has UF_HIDDEN,
+    /// as set by `chflags hidden`, like Finder does, unless this is true,
+    /// while elsewhere this does nothing
+    pub fn ignore_finder_flags(&mut self, if_ignore: bool) -> &mut Self {
+        self.ignore_finder_flags = if_ignore;
+        self
+    }
+
+    /// list only entries with all the file attributes of the mask, only on windows,
+    /// e.g. with_attributes(0x20) lists the entries with FILE_ATTRIBUTE_ARCHIVE for a backup
+    ///
+    /// the attributes are the ones of `MetadataExt::file_attributes`,
+    /// and the masks of several calls add up
+    #[cfg(windows)]
+    pub fn with_attributes(&mut self, mask: u32) -> &mut Self {
+        self.with_attributes |= mask;
+        self
+    }
+
+    /// list only entries with none of the file attributes of the mask, only on windows,
+    /// e.g. without_attributes(0x4) skips the entries with FILE_ATTRIBUTE_SYSTEM,
+    /// see [`ListOption::with_attributes`]
+    #[cfg(windows)]
+    pub fn without_attributes(&mut self, mask: u32) -> &mut Self {
+        self.without_attributes |= mask;
+        self
+    }
+
+    /// set if a file with several hardlinks is only listed by the first path found,
+    /// default false
+    ///
+    /// the files are told apart by their device and inode on unix, and by their volume
+    /// and file index on windows, and only the ones with more than one link are remembered,
+    /// so a tree without hardlinks costs nothing more
+    ///
+    /// it only works within one listing, e.g. [`ListOption::list_many`] may list
+    /// the same file from two roots
+    pub fn dedup_hardlinks(&mut self, if_dedup: bool) -> &mut Self {
+        self.dedup_hardlinks = if_dedup;
+        self
+    }
+
+    /// list only entries last accessed at this time or later, the bound included
+    ///
+    /// unlike the modification time, when the access time isn't available,
+    /// the entry is skipped without any error, and note that many file systems
+    /// are mounted with `noatime` or `relatime`, which update it rarely
+    pub fn accessed_after(&mut self, time: SystemTime) -> &mut Self {
+        self.accessed_after = Some(time);
+        self
+    }
+
+    /// list only entries last accessed at this time or earlier, see [`ListOption::accessed_after`]
+    pub fn accessed_before(&mut self, time: SystemTime) -> &mut Self {
+        self.accessed_before = Some(time);
+        self
+    }
+
+    /// list only entries created at this time or later, the bound included
+    ///
+    /// the creation time isn't available on every platform and file system,
+    /// e.g. on some linux file systems, and then the entry is skipped without any error
+    pub fn created_after(&mut self, time: SystemTime) -> &mut Self {
+        self.created_after = Some(time);
+        self
+    }
+
+    /// list only entries created at this time or earlier, see [`ListOption::created_after`]
+    pub fn created_before(&mut self, time: SystemTime) -> &mut Self {
+        self.created_before = Some(time);
+        self
+    }
+}
+
+// what the time filters compare against, taken once when a listing starts
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct ListStart {
+    pub(crate) now: SystemTime,
+    // the modification time of the newer_than_path reference, None if it doesn't exist
+    pub(crate) reference: Option<SystemTime>,
+    // the real user id of the process
+    #[cfg(unix)]
+    pub(crate) uid: u32,
+}
+
+// the checks of the metadata of an entry
+impl ListOption {
+    pub(crate) fn start(&self) -> ListStart {
+        ListStart {
+            now: SystemTime::now(),
+            reference: self
+                .newer_than_path
+                .as_ref()
+                .and_then(|path| fs::metadata(path).and_then(|m| m.modified()).ok()),
+            // SAFETY: getuid has no precondition and never fails
+            #[cfg(unix)]
+            uid: unsafe { libc::getuid() },
+        }
+    }
+
+    #[cfg(unix)]
+    fn is_executable(&self, candidate: &Candidate) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        candidate.metadata.is_file() && candidate.metadata.permissions().mode() & 0o111 != 0
+    }
+
+    #[cfg(not(unix))]
+    fn is_executable(&self, candidate: &Candidate) -> bool {
+        candidate.metadata.is_file()
+            && candidate.path.file_name().is_some_and(|name| {
+                self.executable_exts
+                    .iter()
+                    .any(|ext| has_extension(name.as_encoded_bytes(), ext.as_bytes(), true))
+            })
+    }
+
+    // check if the entry is hidden by its attributes, whatever its name
+    #[cfg(windows)]
+    pub(crate) fn is_hidden_by_attribute(&self, metadata: &Metadata) -> bool {
+        use std::os::windows::fs::MetadataExt;
+        // FILE_ATTRIBUTE_HIDDEN
+        self.hidden_by_attribute && metadata.file_attributes() & 0x2 != 0
+    }
+
+    #[cfg(target_os = "macos")]
+    pub(crate) fn is_hidden_by_attribute(&self, metadata: &Metadata) -> bool {
+        use std::os::macos::fs::MetadataExt;
+        // UF_HIDDEN
+        !self.ignore_finder_flags && metadata.st_flags() & 0x8000 != 0
+    }
+
+    #[cfg(not(any(windows, target_os = "macos")))]
+    pub(crate) fn is_hidden_by_attribute(&self, _metadata: &Metadata) -> bool {
+        false
+    }
+
+    // check if an entry owned by the ids passes the owner filters,
+    // with current_uid being the user of the process
+    #[cfg(unix)]
+    pub(crate) fn show_owner(&self, uid: u32, gid: u32, current_uid: u32) -> bool {
+        self.uid.is_none_or(|only| uid == only)
+            && self.gid.is_none_or(|only| gid == only)
+            && (!self.not_owned_by_me || uid != current_uid)
+    }
+
+    pub(crate) fn show_metadata(&self, candidate: &Candidate) -> Result<bool, ListError> {
+        let Candidate { path, metadata, .. } = *candidate;
+        let check_size = || {
+            let len = metadata.len();
+            !metadata.is_file()
+                || (self.min_size.is_none_or(|min| len >= min)
+                    && self.max_size.is_none_or(|max| len <= max))
+        };
+        let check_empty = || {
+            // a symlink to anything but a directory is a file which is never empty
+            let is_file = metadata.is_file() || (candidate.is_symlink && !metadata.is_dir());
+            let is_empty = !candidate.is_symlink && metadata.len() == 0;
+            !is_file || ((!self.only_empty || is_empty) && (!self.skip_empty || !is_empty))
+        };
+        let check_empty_dir = || {
+            !self.only_empty_dirs
+                || !metadata.is_dir()
+                || fs::read_dir(path).is_ok_and(|mut entries| entries.next().is_none())
+        };
+        let check_readonly = || {
+            let readonly = metadata.permissions().readonly();
+            !(metadata.is_file() || (self.readonly_on_dirs && metadata.is_dir()))
+                || ((!self.only_readonly || readonly) && (!self.skip_readonly || !readonly))
+        };
+        #[cfg(unix)]
+        let check_unix = || {
+            use std::os::unix::fs::MetadataExt;
+            let mode = metadata.mode() & 0o7777;
+            self.mode_any.is_none_or(|mask| mode & mask != 0)
+                && self.mode_all.is_none_or(|mask| mode & mask == mask)
+                && self.mode_exact.is_none_or(|exact| mode == exact)
+                && self.show_owner(metadata.uid(), metadata.gid(), candidate.start.uid)
+                && (!metadata.is_file()
+                    || (self.min_links.is_none_or(|min| metadata.nlink() >= min)
+                        && self.max_links.is_none_or(|max| metadata.nlink() <= max)))
+                && (!self.only_sparse
+                    || !metadata.is_file()
+                    || metadata
+                        .blocks()
+                        .saturating_mul(512)
+                        .saturating_add(self.sparse_threshold)
+                        <= metadata.len())
+        };
+        #[cfg(not(unix))]
+        let check_unix = || true;
+        #[cfg(windows)]
+        let check_windows = || {
+            use std::os::windows::fs::MetadataExt;
+            let attributes = metadata.file_attributes();
+            attributes & self.with_attributes == self.with_attributes
+                && attributes & self.without_attributes == 0
+        };
+        #[cfg(not(windows))]
+        let check_windows = || true;
+        if !(check_size()
+            && check_empty()
+            && check_empty_dir()
+            && check_readonly()
+            && check_unix()
+            && check_windows()
+            && (!self.only_executable || self.is_executable(candidate))
+            && (!self.readable || is_readable(candidate))
+            && (!self.writable || is_writable(candidate)))
+        {
+            return Ok(false);
+        }
+        Ok(self.show_modified(candidate)?
+            && show_time(
+                || candidate.metadata.accessed(),
+                self.accessed_after,
+                self.accessed_before,
+            )
+            && show_time(
+                || candidate.metadata.created(),
+                self.created_after,
+                self.created_before,
+            ))
+    }
+
+    fn show_modified(&self, candidate: &Candidate) -> Result<bool, ListError> {
+        if self.modified_after.is_none()
+            && self.modified_before.is_none()
+            && self.older_than.is_none()
+            && self.newer_than.is_none()
+            && candidate.start.reference.is_none()
+        {
+            return Ok(true);
+        }
+        let modified = candidate
+            .metadata
+            .modified()
+            .map_err(|source| ListError::Metadata {
+                path: candidate.path.to_path_buf(),
+                source,
+            })?;
+        // None when the cutoff would be before the earliest time representable
+        let cutoff = |age: Duration| candidate.start.now.checked_sub(age);
+        Ok(self.modified_after.is_none_or(|after| modified >= after)
+            && self.modified_before.is_none_or(|before| modified <= before)
+            && self
+                .older_than
+                .is_none_or(|age| cutoff(age).is_some_and(|cutoff| modified <= cutoff))
+            && self
+                .newer_than
+                .is_none_or(|age| cutoff(age).is_none_or(|cutoff| modified >= cutoff))
+            && candidate
+                .start
+                .reference
+                .is_none_or(|reference| modified > reference))
+    }
+}
+
+// check if the time is within the bounds, an entry without the time is not shown
+fn show_time<F>(time: F, after: Option<SystemTime>, before: Option<SystemTime>) -> bool
+where
+    F: FnOnce() -> io::Result<SystemTime>,
+{
+    if after.is_none() && before.is_none() {
+        return true;
+    }
+    time().is_ok_and(|time| {
+        after.is_none_or(|after| time >= after) && before.is_none_or(|before| time <= before)
+    })
+}
+
+#[cfg(unix)]
+fn is_readable(candidate: &Candidate) -> bool {
+    access(candidate.path, libc::R_OK)
+}
+
+#[cfg(not(unix))]
+fn is_readable(_candidate: &Candidate) -> bool {
+    true
+}
+
+#[cfg(unix)]
+fn is_writable(candidate: &Candidate) -> bool {
+    access(candidate.path, libc::W_OK)
+}
+
+#[cfg(not(unix))]
+fn is_writable(candidate: &Candidate) -> bool {
+    !candidate.metadata.permissions().readonly()
+}
+
+// check if the process may access the path in the mode, by its effective ids
+#[cfg(unix)]
+fn access(path: &Path, mode: libc::c_int) -> bool {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+    let Ok(path) = CString::new(path.as_os_str().as_bytes()) else {
+        return false;
+    };
+    // SAFETY: the path is a valid nul terminated string, which lives through the call
+    unsafe { libc::faccessat(libc::AT_FDCWD, path.as_ptr(), mode, libc::AT_EACCESS) == 0 }
+}
+
+// the id of the file, if it is a file with several hardlinks
+#[cfg(unix)]
+pub(crate) fn hardlink_id(_path: &Path, metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    (metadata.is_file() && metadata.nlink() > 1).then(|| (metadata.dev(), metadata.ino()))
+}
+
+#[cfg(windows)]
+pub(crate) fn hardlink_id(path: &Path, metadata: &Metadata) -> Option<(u64, u64)> {
+    if !metadata.is_file() {
+        return None;
+    }
+    let handle = winapi_util::Handle::from_path_any(path).ok()?;
+    let info = winapi_util::file::information(&handle).ok()?;
+    (info.number_of_links() > 1).then(|| (info.volume_serial_number(), info.file_index()))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn hardlink_id(_path: &Path, _metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+// the id of the file, which tells if two paths are the same file
+#[cfg(unix)]
+pub(crate) fn file_id(_path: &Path, metadata: &Metadata) -> Option<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Some((metadata.dev(), metadata.ino()))
+}
+
+#[cfg(windows)]
+pub(crate) fn file_id(path: &Path, _metadata: &Metadata) -> Option<(u64, u64)> {
+    let handle = winapi_util::Handle::from_path_any(path).ok()?;
+    let info = winapi_util::file::information(&handle).ok()?;
+    Some((info.volume_serial_number(), info.file_index()))
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn file_id(_path: &Path, _metadata: &Metadata) -> Option<(u64, u64)> {
+    None
+}
+
+// check if the path itself is a junction, i.e. a reparse point with the mount point tag
+#[cfg(windows)]
+pub(crate) fn is_junction(path: &Path) -> bool {
+    use std::{
+        fs::OpenOptions,
+        os::windows::{
+            fs::{MetadataExt, OpenOptionsExt},
+            io::AsRawHandle,
+        },
+    };
+    use windows_sys::Win32::{
+        Storage::FileSystem::{
+            FileAttributeTagInfo, GetFileInformationByHandleEx, FILE_ATTRIBUTE_REPARSE_POINT,
+            FILE_ATTRIBUTE_TAG_INFO, FILE_FLAG_BACKUP_SEMANTICS, FILE_FLAG_OPEN_REPARSE_POINT,
+        },
+        System::SystemServices::IO_REPARSE_TAG_MOUNT_POINT,
+    };
+
+    let is_reparse_point = fs::symlink_metadata(path)
+        .is_ok_and(|metadata| metadata.file_attributes() & FILE_ATTRIBUTE_REPARSE_POINT != 0);
+    if !is_reparse_point {
+        return false;
+    }
+    // the reparse point itself, not what it points to
+    let Ok(file) = OpenOptions::new()
+        .access_mode(0)
+        .custom_flags(FILE_FLAG_OPEN_REPARSE_POINT | FILE_FLAG_BACKUP_SEMANTICS)
+        .open(path)
+    else {
+        return false;
+    };
+    let mut info = FILE_ATTRIBUTE_TAG_INFO {
+        FileAttributes: 0,
+        ReparseTag: 0,
+    };
+    // SAFETY: the handle is open for the call, and the buffer is the size given
+    let ok = unsafe {
+        GetFileInformationByHandleEx(
+            file.as_raw_handle(),
+            FileAttributeTagInfo,
+            (&mut info as *mut FILE_ATTRIBUTE_TAG_INFO).cast(),
+            std::mem::size_of::<FILE_ATTRIBUTE_TAG_INFO>() as u32,
+        )
+    };
+    ok != 0 && info.ReparseTag == IO_REPARSE_TAG_MOUNT_POINT
+}
+
+#[cfg(not(windows))]
+pub(crate) fn is_junction(_path: &Path) -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fs::File,
+        path::Path,
+        time::{Duration, SystemTime},
+    };
+
+    use super::show_time;
+    use crate::{test_dir::TestDir, ListOption};
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    // set the attributes of the path with the attrib command, e.g. "+h"
+    #[cfg(windows)]
+    fn attrib(path: &Path, change: &str) {
+        let status = std::process::Command::new("attrib")
+            .arg(change)
+            .arg(path)
+            .status()
+            .unwrap();
+        assert!(status.success());
+    }
+
+    #[cfg(unix)]
+    fn set_mode(path: &Path, mode: u32) {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(mode)).unwrap();
+    }
+
+    fn set_modified(path: &Path, time: SystemTime) {
+        File::options()
+            .write(true)
+            .open(path)
+            .unwrap()
+            .set_modified(time)
+            .unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn show_owner_checks_each_id() {
+        let option = ListOption::default();
+        assert!(option.show_owner(1000, 100, 1000));
+
+        let mut option = ListOption::default();
+        option.owned_by_uid(1000);
+        assert!(option.show_owner(1000, 100, 0));
+        assert!(!option.show_owner(1001, 100, 0));
+
+        option.owned_by_gid(100);
+        assert!(option.show_owner(1000, 100, 0));
+        assert!(!option.show_owner(1000, 101, 0));
+
+        let mut option = ListOption::default();
+        option.not_owned_by_current_user(true);
+        assert!(!option.show_owner(1000, 100, 1000));
+        assert!(option.show_owner(0, 100, 1000));
+        // owned by the uid asked for, but that is the current user
+        option.owned_by_uid(1000);
+        assert!(!option.show_owner(1000, 100, 1000));
+        option.not_owned_by_current_user(false);
+        assert!(option.show_owner(1000, 100, 1000));
+    }
+
+    #[test]
+    fn size_bounds_are_included() {
+        let dir = TestDir::new();
+        dir.file("small", &[0; 10]);
+        dir.file("exact", &[0; 100]);
+        dir.file("big", &[0; 1000]);
+        dir.file("sub/empty", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false).min_size(100);
+        // directories are not filtered
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["big", "exact", "sub"]
+        );
+        option.max_size(100);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["exact", "sub"]);
+        let mut option = ListOption::default();
+        option.recursive(true).only_file().max_size(0);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["sub/empty"]);
+    }
+
+    #[test]
+    fn empty_files() {
+        let dir = TestDir::new();
+        dir.file("a.log", b"");
+        dir.file("b.log", b"log");
+        dir.file("sub/c.log", b"");
+        std::fs::create_dir(dir.join("void")).unwrap();
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .only_empty_files(true);
+        // directories, empty or not, are not filtered
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a.log", "sub", "sub/c.log", "void"]
+        );
+        option.only_empty_files(false).skip_empty_files(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["b.log", "sub", "void"]
+        );
+        option.only_empty_files(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["sub", "void"]);
+        // an empty directory is not an empty file
+        option.skip_empty_files(false).only_file();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a.log", "sub/c.log"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn empty_files_through_symlinks() {
+        let dir = TestDir::new();
+        dir.file("empty", b"");
+        dir.file("full", b"x");
+        dir.symlink("empty", "to_empty");
+        dir.symlink("full", "to_full");
+        let mut option = ListOption::default();
+        option.include_root(false).only_empty_files(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["empty"]);
+        option.only_empty_files(false).skip_empty_files(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["full", "to_empty", "to_full"]
+        );
+        // the same when the symlinks are not followed
+        option.follow_symlinks(false);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["full", "to_empty", "to_full"]
+        );
+        option.skip_empty_files(false).only_empty_files(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["empty"]);
+    }
+
+    #[test]
+    fn empty_dirs() {
+        let dir = TestDir::new();
+        for path in ["a/b/c/x.rs", "a/d/y.txt", "e/.keep"] {
+            dir.file(path, b"");
+        }
+        std::fs::create_dir_all(dir.join("f/g")).unwrap();
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false).only_empty_dirs();
+        // a hidden entry counts
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["f/g"]);
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .ext("rs")
+            .prune_empty_dirs(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a", "a/b", "a/b/c", "a/b/c/x.rs"]
+        );
+        // a directory beyond the level is still listed
+        option.level(1);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a", "e", "f"]);
+    }
+
+    #[test]
+    fn dirs_with_matches_only() {
+        let dir = TestDir::new();
+        for path in ["a/b/c/x.rs", "a/d/y.txt", "e/f/g/z.txt"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .ext("rs")
+            .dirs_with_matches_only(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a", "a/b", "a/b/c", "a/b/c/x.rs"]
+        );
+        // a directory beyond the level isn't read, so it is never listed
+        option.level(3);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), Vec::<String>::new());
+        option.level(4).clear_sufs().only_dir();
+        assert_eq!(dir.rel(option.list_paths(dir.path())), Vec::<String>::new());
+    }
+
+    #[test]
+    fn modified_between_the_bounds() {
+        let dir = TestDir::new();
+        let now = SystemTime::now();
+        for (name, age) in [("old", 10), ("mid", 5), ("new", 0)] {
+            set_modified(&dir.file(name, b""), now - DAY * age);
+        }
+        dir.file("sub/inner", b"");
+        set_modified(&dir.join("sub/inner"), now - DAY * 5);
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .only_file()
+            .modified_after(now - DAY * 5);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["mid", "new", "sub/inner"]
+        );
+        // the bounds are included
+        option.modified_before(now - DAY * 5);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["mid", "sub/inner"]);
+        // a directory filtered out is still descended into
+        let mut option = ListOption::default();
+        option.recursive(true).modified_before(now - DAY);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["mid", "old", "sub/inner"]
+        );
+    }
+
+    #[test]
+    fn ages_from_the_start_of_the_listing() {
+        let dir = TestDir::new();
+        let now = SystemTime::now();
+        set_modified(&dir.file("old.log", b""), now - DAY * 40);
+        set_modified(&dir.file("new.log", b""), now - DAY);
+        let mut option = ListOption::default();
+        option.include_root(false).ext("log").older_than(DAY * 30);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["old.log"]);
+        let mut option = ListOption::default();
+        option.include_root(false).newer_than(DAY * 30);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["new.log"]);
+        // beyond the earliest time, everything is newer and nothing is older
+        option.newer_than(Duration::MAX);
+        assert_eq!(option.count(dir.path()), 2);
+        let mut option = ListOption::default();
+        option.include_root(false).older_than(Duration::MAX);
+        assert_eq!(option.count(dir.path()), 0);
+    }
+
+    #[test]
+    fn show_time_without_the_time() {
+        let now = SystemTime::now();
+        let time = || Ok(now);
+        let unsupported = || Err(std::io::Error::from(std::io::ErrorKind::Unsupported));
+        assert!(show_time(time, None, None));
+        assert!(show_time(unsupported, None, None));
+        assert!(show_time(time, Some(now), Some(now)));
+        assert!(!show_time(time, Some(now + DAY), None));
+        assert!(!show_time(time, None, Some(now - DAY)));
+        // skipped without any error when the time isn't available
+        assert!(!show_time(unsupported, Some(now - DAY), None));
+    }
+
+    #[test]
+    fn accessed_and_created_times() {
+        let dir = TestDir::new();
+        let path = dir.file("a", b"");
+        let metadata = std::fs::metadata(&path).unwrap();
+        let mut option = ListOption::default();
+        option
+            .include_root(false)
+            .accessed_before(SystemTime::now() + DAY);
+        assert_eq!(
+            option.count(dir.path()),
+            metadata.accessed().is_ok() as usize
+        );
+        option.accessed_after(SystemTime::now() + DAY);
+        assert_eq!(option.count(dir.path()), 0);
+        let mut option = ListOption::default();
+        option
+            .include_root(false)
+            .created_after(SystemTime::UNIX_EPOCH);
+        assert_eq!(
+            option.count(dir.path()),
+            metadata.created().is_ok() as usize
+        );
+        option.created_before(SystemTime::UNIX_EPOCH);
+        assert_eq!(option.count(dir.path()), 0);
+    }
+
+    #[test]
+    fn newer_than_a_reference_file() {
+        let dir = TestDir::new();
+        let now = SystemTime::now();
+        let reference = dir.file("target/lib.rlib", b"");
+        set_modified(&reference, now - DAY);
+        set_modified(&dir.file("src/changed.rs", b""), now);
+        set_modified(&dir.file("src/same.rs", b""), now - DAY);
+        set_modified(&dir.file("src/old.rs", b""), now - DAY * 2);
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .ext("rs")
+            .only_file()
+            .newer_than_path(&reference);
+        // strictly after the reference
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["src/changed.rs"]);
+        // nothing is filtered when the reference doesn't exist
+        option.newer_than_path(dir.join("target/missing"));
+        assert_eq!(option.count(dir.path()), 3);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn permission_masks() {
+        let dir = TestDir::new();
+        for (name, mode) in [
+            ("private", 0o600),
+            ("shared", 0o644),
+            ("open", 0o666),
+            ("run", 0o755),
+        ] {
+            set_mode(&dir.file(name, b""), mode);
+        }
+        let mut option = ListOption::default();
+        option.include_root(false).mode_any(0o022);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["open"]);
+        let mut option = ListOption::default();
+        option.include_root(false).mode_all(0o444);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["open", "run", "shared"]
+        );
+        option.mode_exact(0o644);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["shared"]);
+    }
+
+    #[test]
+    fn executable_files() {
+        let dir = TestDir::new();
+        for path in ["script", "tool.EXE", "notes.txt", "bin/inner.cmd"] {
+            dir.file(path, b"");
+        }
+        #[cfg(unix)]
+        set_mode(&dir.join("script"), 0o744);
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .only_executable(true);
+        // a directory never is
+        #[cfg(unix)]
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["script"]);
+        #[cfg(not(unix))]
+        {
+            assert_eq!(
+                dir.rel(option.list_paths(dir.path())),
+                ["bin/inner.cmd", "tool.EXE"]
+            );
+            option.executable_exts(["txt"]);
+            assert_eq!(dir.rel(option.list_paths(dir.path())), ["notes.txt"]);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn readable_and_writable_by_the_process() {
+        let dir = TestDir::new();
+        set_mode(&dir.file("read_only", b""), 0o400);
+        set_mode(&dir.file("write_only", b""), 0o200);
+        dir.file("both", b"");
+        let mut readable = ListOption::default();
+        readable.include_root(false).readable(true);
+        let mut writable = ListOption::default();
+        writable.include_root(false).writable(true);
+        // root may read and write anything
+        if crate::test_dir::is_root() {
+            assert_eq!(readable.count(dir.path()), 3);
+            return;
+        }
+        assert_eq!(
+            dir.rel(readable.list_paths(dir.path())),
+            ["both", "read_only"]
+        );
+        assert_eq!(
+            dir.rel(writable.list_paths(dir.path())),
+            ["both", "write_only"]
+        );
+    }
+
+    #[test]
+    fn readonly_files() {
+        let dir = TestDir::new();
+        let locked = dir.file("locked", b"");
+        dir.file("open", b"");
+        let sub = dir.join("sub");
+        std::fs::create_dir(&sub).unwrap();
+        for path in [&locked, &sub] {
+            let mut permissions = std::fs::metadata(path).unwrap().permissions();
+            permissions.set_readonly(true);
+            std::fs::set_permissions(path, permissions).unwrap();
+        }
+        let mut option = ListOption::default();
+        option.include_root(false).only_readonly(true);
+        // directories are not filtered by default
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["locked", "sub"]);
+        option.only_readonly(false).skip_readonly(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["open", "sub"]);
+        option.apply_readonly_to_dirs(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["open"]);
+        // made writable again, so that the dir can be removed everywhere
+        for path in [&locked, &sub] {
+            let mut permissions = std::fs::metadata(path).unwrap().permissions();
+            #[allow(clippy::permissions_set_readonly_false)]
+            permissions.set_readonly(false);
+            std::fs::set_permissions(path, permissions).unwrap();
+        }
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn windows_attribute_masks() {
+        let dir = TestDir::new();
+        let archived = dir.file("archived", b"");
+        let system = dir.file("system", b"");
+        attrib(&archived, "+a");
+        attrib(&system, "-a");
+        attrib(&system, "+s");
+        let mut option = ListOption::default();
+        // FILE_ATTRIBUTE_ARCHIVE
+        option.include_root(false).with_attributes(0x20);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["archived"]);
+        // FILE_ATTRIBUTE_SYSTEM
+        let mut option = ListOption::default();
+        option.include_root(false).without_attributes(0x4);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["archived"]);
+        // the masks add up
+        option
+            .with_attributes(0x20)
+            .with_attributes(0x4)
+            .without_attributes(0);
+        assert!(option.list_paths(dir.path()).is_empty());
+        attrib(&system, "-s");
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn hidden_attribute_counts_as_hidden() {
+        let dir = TestDir::new();
+        attrib(&dir.file("secret.txt", b""), "+h");
+        dir.file("hidden_dir/inner.txt", b"");
+        attrib(&dir.join("hidden_dir"), "+h");
+        dir.file("plain.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["plain.txt"]);
+        option.hidden_by_attribute(false);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            [
+                "hidden_dir",
+                "hidden_dir/inner.txt",
+                "plain.txt",
+                "secret.txt"
+            ]
+        );
+        // a hidden root is still listed and read
+        let mut option = ListOption::default();
+        option.recursive(true);
+        assert_eq!(option.count(dir.join("hidden_dir")), 2);
+    }
+
+    #[cfg(target_os = "macos")]
+    #[test]
+    fn finder_hidden_flag_counts_as_hidden() {
+        let dir = TestDir::new();
+        let secret = dir.file("secret.txt", b"");
+        dir.file("plain.txt", b"");
+        let status = std::process::Command::new("chflags")
+            .arg("hidden")
+            .arg(&secret)
+            .status()
+            .unwrap();
+        assert!(status.success());
+        let mut option = ListOption::default();
+        option.include_root(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["plain.txt"]);
+        option.ignore_finder_flags(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["plain.txt", "secret.txt"]
+        );
+        option.ignore_finder_flags(false).only_hidden();
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["secret.txt"]);
+    }
+
+    #[test]
+    fn hardlinks_listed_once() {
+        let dir = TestDir::new();
+        let first = dir.file("a/first", b"");
+        std::fs::create_dir(dir.join("b")).unwrap();
+        std::fs::hard_link(&first, dir.join("b/second")).unwrap();
+        dir.file("single", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).only_file().sorted(true);
+        assert_eq!(option.count(dir.path()), 3);
+        option.dedup_hardlinks(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a/first", "single"]
+        );
+        // only within one listing
+        assert_eq!(option.list_many([dir.join("b"), dir.join("a")]).len(), 2);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hardlink_counts() {
+        let dir = TestDir::new();
+        let linked = dir.file("linked", b"");
+        std::fs::hard_link(&linked, dir.join("other")).unwrap();
+        dir.file("single", b"");
+        let mut option = ListOption::default();
+        option.include_root(false).min_links(2);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["linked", "other"]);
+        let mut option = ListOption::default();
+        option.include_root(false).max_links(1);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["single"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn sparse_files() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = TestDir::new();
+        let sparse = dir.file("sparse", b"");
+        File::options()
+            .write(true)
+            .open(&sparse)
+            .unwrap()
+            .set_len(1 << 20)
+            .unwrap();
+        dir.file("dense", &[1; 8192]);
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false).only_sparse(true);
+        // the file system may not support sparse files
+        let metadata = std::fs::metadata(&sparse).unwrap();
+        if metadata.blocks() * 512 >= metadata.len() {
+            assert!(option.list_paths(dir.path()).is_empty());
+            return;
+        }
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["sparse"]);
+        option.sparse_threshold(2 << 20);
+        assert!(option.list_paths(dir.path()).is_empty());
+    }
+}
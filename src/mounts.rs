@@ -0,0 +1,148 @@
+#[cfg(target_os = "linux")]
+use std::collections::HashSet;
+use std::fs::Metadata;
+
+use crate::ListOption;
+
+/// skip the pseudo filesystems of the system
+impl ListOption {
+    /// set if the directories on a pseudo filesystem, like /proc, /sys or /dev,
+    /// are not descended into, default false, only on linux
+    ///
+    /// their entries are volatile or generated by the kernel, so reading them is never
+    /// what a file listing wants, and the mounts are read once when a listing starts,
+    /// from /proc/self/mountinfo, while the mount points themselves are still listed,
+    /// and a root on one is still read, e.g. listing /proc lists its entries
+    ///
+    /// on the other platforms it does nothing
+    pub fn skip_pseudo_filesystems(&mut self, if_skip: bool) -> &mut Self {
+        self.skip_pseudo_fs = if_skip;
+        self
+    }
+}
+
+// the filesystem types holding no real files
+#[cfg(target_os = "linux")]
+const PSEUDO_TYPES: [&str; 21] = [
+    "autofs",
+    "binfmt_misc",
+    "bpf",
+    "cgroup",
+    "cgroup2",
+    "configfs",
+    "debugfs",
+    "devpts",
+    "devtmpfs",
+    "efivarfs",
+    "fusectl",
+    "hugetlbfs",
+    "mqueue",
+    "nsfs",
+    "proc",
+    "pstore",
+    "rpc_pipefs",
+    "securityfs",
+    "selinuxfs",
+    "sysfs",
+    "tracefs",
+];
+
+// the devices of the pseudo filesystems mounted when the listing started
+#[derive(Default)]
+pub(crate) struct PseudoFs {
+    #[cfg(target_os = "linux")]
+    devices: HashSet<u64>,
+}
+
+impl PseudoFs {
+    // read the mounts, none if they can't be read
+    #[cfg(target_os = "linux")]
+    pub(crate) fn load() -> Self {
+        let mountinfo = std::fs::read_to_string("/proc/self/mountinfo").unwrap_or_default();
+        Self {
+            devices: parse_mountinfo(&mountinfo),
+        }
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn load() -> Self {
+        Self {}
+    }
+
+    // check if the metadata is the one of an entry on a pseudo filesystem
+    #[cfg(target_os = "linux")]
+    pub(crate) fn contains(&self, metadata: &Metadata) -> bool {
+        use std::os::unix::fs::MetadataExt;
+        self.devices.contains(&metadata.dev())
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    pub(crate) fn contains(&self, _metadata: &Metadata) -> bool {
+        false
+    }
+}
+
+// the devices of the pseudo filesystems in the mountinfo, whose lines look like
+// `25 28 0:6 / /dev rw,relatime - devtmpfs devtmpfs rw`, with the device third
+// and the filesystem type right after the `-`
+#[cfg(target_os = "linux")]
+fn parse_mountinfo(mountinfo: &str) -> HashSet<u64> {
+    mountinfo
+        .lines()
+        .filter_map(|line| {
+            let (mount, fs) = line.split_once(" - ")?;
+            let fs_type = fs.split(' ').next()?;
+            if !PSEUDO_TYPES.contains(&fs_type) {
+                return None;
+            }
+            let (major, minor) = mount.split(' ').nth(2)?.split_once(':')?;
+            let device = libc::makedev(major.parse().ok()?, minor.parse().ok()?);
+            Some(device as u64)
+        })
+        .collect()
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::path::Path;
+
+    use super::parse_mountinfo;
+    use crate::ListOption;
+
+    const MOUNTINFO: &str = "\
+22 1 8:1 / / rw,relatime shared:1 - ext4 /dev/sda1 rw
+23 22 0:21 / /proc rw,nosuid,nodev,noexec,relatime shared:5 - proc proc rw
+24 22 0:22 / /sys rw,nosuid,nodev,noexec,relatime shared:6 - sysfs sysfs rw
+25 22 0:6 / /dev rw,relatime - devtmpfs devtmpfs rw
+26 22 0:23 / /tmp rw,relatime - tmpfs tmpfs rw
+27 24 0:24 / /sys/fs/cgroup rw,relatime shared:9 - cgroup2 cgroup2 rw
+not a mount
+28 22 x:1 / /bad rw - proc proc rw
+";
+
+    #[test]
+    fn parses_the_pseudo_devices_only() {
+        let devices = parse_mountinfo(MOUNTINFO);
+        let expected = [(0, 21), (0, 22), (0, 6), (0, 24)]
+            .map(|(major, minor)| libc::makedev(major, minor) as u64);
+        assert_eq!(devices, expected.into_iter().collect());
+        assert!(parse_mountinfo("").is_empty());
+    }
+
+    #[test]
+    fn proc_is_listed_but_not_read() {
+        if !Path::new("/proc/self").exists() {
+            return;
+        }
+        let mut option = ListOption::default();
+        option.level(2).skip_pseudo_filesystems(true);
+        let listed = option.list_paths("/");
+        assert!(listed.iter().any(|path| path == Path::new("/proc")));
+        assert!(!listed
+            .iter()
+            .any(|path| path.parent() == Some(Path::new("/proc"))));
+        // a root on one is still read
+        assert!(option.has_match("/proc/self"));
+        assert!(option.count("/proc") > 1);
+    }
+}
@@ -0,0 +1,1042 @@
+use std::{
+    borrow::Cow,
+    ffi::OsStr,
+    fs::Metadata,
+    path::{Component, Path},
+};
+
+use crate::{Candidate, EntryKind, ListOption};
+
+/// filter entries by their file name
+impl ListOption {
+    /// append one prefix to the allowed prefixes
+    ///
+    /// only entries whose file name starts with one of the allowed prefixes will be listed,
+    /// on top of the suffix filters, e.g. prefix("backup_").ext("sql") lists backup_2024.sql
+    ///
+    /// the hidden rules still apply, e.g. prefix(".") lists nothing unless hidden entries are shown
+    pub fn prefix(&mut self, prefix: &str) -> &mut Self {
+        push_unique(&mut self.prefixes, prefix);
+        self
+    }
+
+    /// replace the allowed prefixes with these, see [`ListOption::prefix`]
+    pub fn prefixes<I, S>(&mut self, prefixes: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.prefixes.clear();
+        for prefix in prefixes {
+            self.prefix(prefix.as_ref());
+        }
+        self
+    }
+
+    /// append one name to the allowed names
+    ///
+    /// only entries whose file name is exactly one of the allowed names will be listed,
+    /// e.g. name("Cargo.toml") lists Cargo.toml but neither MyCargo.toml nor Cargo.toml.bak,
+    /// with recursive(true) it finds all of them under the root
+    ///
+    /// a directory with the name is listed too, unless dir(false) is set
+    pub fn name(&mut self, name: &str) -> &mut Self {
+        push_unique(&mut self.names, name);
+        self
+    }
+
+    /// replace the allowed names with these, see [`ListOption::name`]
+    pub fn names<I, S>(&mut self, names: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.names.clear();
+        for name in names {
+            self.name(name.as_ref());
+        }
+        self
+    }
+
+    /// append one stem to the allowed stems
+    ///
+    /// only entries whose file stem, as given by `Path::file_stem`, is exactly one of the allowed
+    /// stems will be listed, e.g. stem("mod") lists mod.rs and mod.ts, but neither mod.rs.bak,
+    /// whose stem is mod.rs, nor module.rs
+    ///
+    /// the stem is the name before its last `.`, so a.tar.gz has the stem a.tar,
+    /// while the stem of a dotfile like .gitignore or of a name without `.` is the whole name
+    ///
+    /// it combines with the ext filters, e.g. stem("index").exts(vec!["html", "htm"])
+    /// lists index.html and index.htm only
+    pub fn stem(&mut self, stem: &str) -> &mut Self {
+        push_unique(&mut self.stems, stem);
+        self
+    }
+
+    /// replace the allowed stems with these, see [`ListOption::stem`]
+    pub fn stems<I, S>(&mut self, stems: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.stems.clear();
+        for stem in stems {
+            self.stem(stem.as_ref());
+        }
+        self
+    }
+
+    /// append one term to the allowed substrings
+    ///
+    /// only entries whose file name contains one of the allowed substrings will be listed,
+    /// the path of the entry is not searched, e.g. name_contains("src") doesn't list src/main.rs
+    pub fn name_contains(&mut self, term: &str) -> &mut Self {
+        push_unique(&mut self.name_contains, term);
+        self
+    }
+
+    /// append one name to the excluded directory names
+    ///
+    /// directories with one of these names are neither listed nor descended into,
+    /// e.g. exclude_dir("target").exclude_dir("node_modules") skips the build outputs,
+    /// while a file named target is still listed
+    ///
+    /// the root itself is never excluded
+    pub fn exclude_dir(&mut self, name: &str) -> &mut Self {
+        push_unique(&mut self.exclude_dirs, name);
+        self
+    }
+
+    /// set if only entries without extension will be listed, default false
+    ///
+    /// an entry has no extension when `Path::extension` finds none, e.g. Makefile or .bashrc,
+    /// while archive. has an empty extension, so it is not listed
+    ///
+    /// it can't be combined with exts, see [`ListOption::validate`],
+    /// while sufs still apply, e.g. suf("_test") with no_extension(true) lists run_test
+    pub fn no_extension(&mut self, if_only: bool) -> &mut Self {
+        self.no_extension = if_only;
+        self
+    }
+
+    /// set if the ext, suf and no_extension filters also apply to directories, default false
+    ///
+    /// by default these filters only apply to files, so e.g. ext("rs") with dir(true)
+    /// still lists all the directories along with the .rs files
+    pub fn apply_sufs_to_dirs(&mut self, if_apply: bool) -> &mut Self {
+        self.sufs_on_dirs = if_apply;
+        self
+    }
+
+    /// set if the suffix and ext filters ignore the case, default false
+    ///
+    /// e.g. with case_insensitive_ext(true), ext("jpg") lists a.JPG and b.jpg,
+    /// only ascii letters are folded, so non-ascii letters must match exactly
+    pub fn case_insensitive_ext(&mut self, if_ignore: bool) -> &mut Self {
+        self.ext_ignore_case = if_ignore;
+        self
+    }
+
+    /// set if all the name filters ignore the case, default false
+    ///
+    /// it applies to the ext, suf, prefix, name, stem, name_contains, exclude_dir, glob
+    /// and reinclude filters, e.g. name("readme.md") lists README.MD and exclude_dir("Target")
+    /// skips target, while name_regex and path_regex are matched as they are, see `(?i)` for them
+    ///
+    /// the case is folded by the unicode lowercase mapping, e.g. `Ä` matches `ä`,
+    /// only ascii letters are folded in names which are not valid utf-8,
+    /// see [`ListOption::case_insensitive_ext`] for ignoring the case of exts and sufs only
+    pub fn case_insensitive(&mut self, if_ignore: bool) -> &mut Self {
+        self.ignore_case = if_ignore;
+        self
+    }
+
+    /// set if the names and the name filters are compared in the unicode NFC form, default false,
+    /// needs the `unicode` feature
+    ///
+    /// e.g. macOS may return `café.txt` decomposed, with `e` followed by a combining accent,
+    /// which name("café.txt") only matches with normalize_unicode(true),
+    /// it applies to the ext, suf, prefix, name, stem, name_contains and exclude_dir filters
+    ///
+    /// when false, names are compared byte by byte, and names which are not valid utf-8
+    /// are always compared so
+    #[cfg(feature = "unicode")]
+    pub fn normalize_unicode(&mut self, if_normalize: bool) -> &mut Self {
+        self.normalize_unicode = if_normalize;
+        self
+    }
+
+    /// set if only the entries whose path can't be created on windows will be listed,
+    /// default false, e.g. to check a tree before packaging it for windows, on any platform
+    ///
+    /// a name is invalid when it has a control character or one of `<>:"/\|?*`,
+    /// ends with `.` or a space, or is a reserved device name like `CON`, `NUL`, `COM1`
+    /// or `LPT1` in any case, with any ext too, e.g. nul.txt, and every name under
+    /// the listing root is checked, so all the entries under an invalid directory are listed
+    pub fn only_windows_invalid_names(&mut self, if_only: bool) -> &mut Self {
+        self.only_windows_invalid = if_only;
+        self
+    }
+}
+
+// the checks of a file name against the name filters
+impl ListOption {
+    // check if the path is a directory excluded by its name, which should not be descended into
+    pub(crate) fn is_excluded_dir(
+        &self,
+        filters: &NameFilters,
+        path: &Path,
+        metadata: &Metadata,
+    ) -> bool {
+        let Some(name) = path
+            .file_name()
+            .filter(|_| metadata.is_dir() && !filters.exclude_dirs.is_empty())
+        else {
+            return false;
+        };
+        let name = filters.fold_name(name);
+        filters.exclude_dirs.iter().any(|dir| **dir == *name.folded)
+    }
+
+    // check if the path is a bundle directory listed as a file, by its name
+    pub(crate) fn is_bundle(&self, path: &Path, metadata: &Metadata) -> bool {
+        self.bundles_as_files
+            && metadata.is_dir()
+            && path.file_name().is_some_and(|name| {
+                self.bundle_exts
+                    .iter()
+                    .any(|ext| has_extension(name.as_encoded_bytes(), ext.as_bytes(), true))
+            })
+    }
+
+    // check if the path is a hidden directory, by its name or by its attributes
+    pub(crate) fn is_hidden_dir(&self, candidate: &Candidate) -> bool {
+        candidate.metadata.is_dir()
+            && (candidate
+                .path
+                .file_name()
+                .is_some_and(|name| name.as_encoded_bytes().starts_with(b"."))
+                || self.is_hidden_by_attribute(candidate.metadata))
+    }
+
+    // the name filters in the form the names are compared in, taken once when a listing
+    // starts, as the case and unicode options may be set after the filters
+    pub(crate) fn name_filters(&self) -> NameFilters {
+        let folding = Folding {
+            ignore_case: self.ignore_case,
+            #[cfg(feature = "unicode")]
+            nfc: self.normalize_unicode,
+        };
+        let fold = |list: &[String]| {
+            list.iter()
+                .map(|s| folding.fold(s.as_bytes()).into_owned())
+                .collect()
+        };
+        NameFilters {
+            folding,
+            exts: fold(&self.exts),
+            sufs: fold(&self.sufs),
+            exclude_exts: fold(&self.exclude_exts),
+            exclude_sufs: fold(&self.exclude_sufs),
+            prefixes: fold(&self.prefixes),
+            names: fold(&self.names),
+            stems: fold(&self.stems),
+            name_contains: fold(&self.name_contains),
+            exclude_dirs: fold(&self.exclude_dirs),
+        }
+    }
+
+    // check the names of the path as listed with only_windows_invalid_names,
+    // which are the names under the root, or the name of the root itself
+    pub(crate) fn show_windows_names(&self, path: &Path, rel_path: Option<&Path>) -> bool {
+        if !self.only_windows_invalid {
+            return true;
+        }
+        match rel_path {
+            Some(rel) => rel.components().any(|component| match component {
+                Component::Normal(name) => is_windows_invalid(name.as_encoded_bytes()),
+                _ => false,
+            }),
+            None => path
+                .file_name()
+                .is_some_and(|name| is_windows_invalid(name.as_encoded_bytes())),
+        }
+    }
+
+    // check if the name is excluded by exclude_ext or exclude_suf
+    pub(crate) fn excludes_name(
+        &self,
+        filters: &NameFilters,
+        name: &FoldedName,
+        kind: EntryKind,
+    ) -> bool {
+        if kind == EntryKind::Dir && !self.sufs_on_dirs {
+            return false;
+        }
+        let name = &*name.folded;
+        let ignore_case = self.ext_ignore_case;
+        filters
+            .exclude_exts
+            .iter()
+            .any(|ext| has_extension(name, ext, ignore_case))
+            || filters
+                .exclude_sufs
+                .iter()
+                .any(|suf| has_suf(name, suf, ignore_case))
+    }
+
+    // check the name against the include filters, see excludes_name for the exclude ones,
+    // hidden_attr is true if the entry is hidden by its attributes whatever its name,
+    // and None for the root, which the hidden and unhidden filters don't apply to
+    pub(crate) fn show_name(
+        &self,
+        filters: &NameFilters,
+        name: &FoldedName,
+        kind: EntryKind,
+        hidden_attr: Option<bool>,
+    ) -> bool {
+        // the suffix filters only apply to files, unless asked otherwise
+        let check_sufs = kind != EntryKind::Dir || self.sufs_on_dirs;
+        let check_no_ext = || !self.no_extension || Path::new(name.raw).extension().is_none();
+        // compare the raw bytes, so names which are not valid utf-8 are fine
+        #[cfg(feature = "regex")]
+        let raw_name = name.raw.as_encoded_bytes();
+        let name = &*name.folded;
+        let check_hidden = || match hidden_attr {
+            None => true,
+            Some(hidden_attr) if hidden_attr || name.starts_with(b".") => self.hidden,
+            Some(_) => self.unhidden,
+        };
+        let check_file_dir = || match kind {
+            EntryKind::File => self.file,
+            EntryKind::Dir => self.dir,
+            EntryKind::Symlink => self.symlink,
+            EntryKind::Fifo => self.fifos,
+            EntryKind::Socket => self.sockets,
+            EntryKind::BlockDevice | EntryKind::CharDevice => self.devices,
+        };
+        let ignore_case = self.ext_ignore_case;
+        let check_ext = || {
+            (filters.exts.is_empty() && filters.sufs.is_empty())
+                || filters
+                    .exts
+                    .iter()
+                    .any(|ext| has_extension(name, ext, ignore_case))
+                || filters
+                    .sufs
+                    .iter()
+                    .any(|suf| has_suf(name, suf, ignore_case))
+        };
+        let check_prefix = || {
+            filters.prefixes.is_empty()
+                || filters
+                    .prefixes
+                    .iter()
+                    .any(|prefix| name.starts_with(prefix))
+        };
+        let check_name = || filters.names.is_empty() || filters.names.iter().any(|n| **n == *name);
+        let check_stem = || {
+            filters.stems.is_empty() || filters.stems.iter().any(|stem| **stem == *file_stem(name))
+        };
+        let check_contains = || {
+            filters.name_contains.is_empty()
+                || filters
+                    .name_contains
+                    .iter()
+                    .any(|term| contains_bytes(name, term))
+        };
+        #[cfg(feature = "regex")]
+        let check_regex = || {
+            self.name_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_match(raw_name))
+        };
+        #[cfg(not(feature = "regex"))]
+        let check_regex = || true;
+        check_hidden()
+            && check_file_dir()
+            && (!check_sufs || (check_ext() && check_no_ext()))
+            && check_prefix()
+            && check_name()
+            && check_stem()
+            && check_contains()
+            && check_regex()
+    }
+}
+
+// the name filters of a listing, folded once, see ListOption::name_filters
+#[derive(Clone, Debug)]
+pub(crate) struct NameFilters {
+    folding: Folding,
+    exts: Vec<Vec<u8>>,
+    sufs: Vec<Vec<u8>>,
+    exclude_exts: Vec<Vec<u8>>,
+    exclude_sufs: Vec<Vec<u8>>,
+    prefixes: Vec<Vec<u8>>,
+    names: Vec<Vec<u8>>,
+    stems: Vec<Vec<u8>>,
+    name_contains: Vec<Vec<u8>>,
+    exclude_dirs: Vec<Vec<u8>>,
+}
+
+impl NameFilters {
+    // bring the file name of an entry to the form the filters are in
+    pub(crate) fn fold_name<'a>(&self, name: &'a OsStr) -> FoldedName<'a> {
+        FoldedName {
+            raw: name,
+            folded: self.folding.fold(name.as_encoded_bytes()),
+        }
+    }
+}
+
+// a file name as listed, along with the form it is compared in
+pub(crate) struct FoldedName<'a> {
+    raw: &'a OsStr,
+    folded: Cow<'a, [u8]>,
+}
+
+// how the names and the name filters are compared, the bytes as is by default
+#[derive(Clone, Copy, Debug)]
+struct Folding {
+    ignore_case: bool,
+    #[cfg(feature = "unicode")]
+    nfc: bool,
+}
+
+impl Folding {
+    // the only place deciding the form the names are compared in
+    fn fold(self, s: &[u8]) -> Cow<'_, [u8]> {
+        let s = self.compose(s);
+        if !self.ignore_case {
+            return s;
+        }
+        match std::str::from_utf8(&s) {
+            Ok(text) => Cow::Owned(text.to_lowercase().into_bytes()),
+            Err(_) => Cow::Owned(s.to_ascii_lowercase()),
+        }
+    }
+
+    // the NFC form of valid utf-8 with normalize_unicode(true)
+    #[cfg(feature = "unicode")]
+    fn compose(self, s: &[u8]) -> Cow<'_, [u8]> {
+        use unicode_normalization::{is_nfc_quick, IsNormalized, UnicodeNormalization};
+        if !self.nfc {
+            return Cow::Borrowed(s);
+        }
+        match std::str::from_utf8(s) {
+            Ok(s) if is_nfc_quick(s.chars()) != IsNormalized::Yes => {
+                Cow::Owned(s.nfc().collect::<String>().into_bytes())
+            }
+            _ => Cow::Borrowed(s),
+        }
+    }
+
+    #[cfg(not(feature = "unicode"))]
+    fn compose(self, s: &[u8]) -> Cow<'_, [u8]> {
+        Cow::Borrowed(s)
+    }
+}
+
+// check if the file name has the ext, which may be compound like `tar.gz`
+//
+// the name must end with `.` and the ext, after a non-empty stem,
+// which gives the same result as `Path::extension` for a simple ext
+pub(crate) fn has_extension(name: &[u8], ext: &[u8], ignore_case: bool) -> bool {
+    if name == b".." || name.len() < ext.len() + 2 {
+        return false;
+    }
+    let dot = name.len() - ext.len() - 1;
+    name[dot] == b'.' && eq(&name[dot + 1..], ext, ignore_case)
+}
+
+// the part of the file name before its last `.`, the same as `Path::file_stem`
+fn file_stem(name: &[u8]) -> &[u8] {
+    if name == b".." {
+        return name;
+    }
+    match name.iter().rposition(|&b| b == b'.') {
+        Some(dot) if dot > 0 => &name[..dot],
+        _ => name,
+    }
+}
+
+// the device names windows reserves, with or without an ext
+const WINDOWS_RESERVED: [&str; 4] = ["CON", "PRN", "AUX", "NUL"];
+// the device names windows reserves when followed by a digit, or by `¹`, `²` or `³`
+const WINDOWS_NUMBERED: [&str; 2] = ["COM", "LPT"];
+
+// check if windows refuses the file name by its naming rules
+fn is_windows_invalid(name: &[u8]) -> bool {
+    if name
+        .iter()
+        .any(|&b| b < 0x20 || b"<>:\"/\\|?*".contains(&b))
+        || name.ends_with(b".")
+        || name.ends_with(b" ")
+    {
+        return true;
+    }
+    // the part before the first `.` is the device, even with spaces after it, e.g. `nul .txt`
+    let device = name.split(|&b| b == b'.').next().unwrap_or(name);
+    let device = match device.iter().rposition(|&b| b != b' ') {
+        Some(end) => &device[..=end],
+        None => device,
+    };
+    WINDOWS_RESERVED
+        .iter()
+        .any(|reserved| eq(device, reserved.as_bytes(), true))
+        || WINDOWS_NUMBERED.iter().any(|numbered| {
+            device.len() > 3
+                && eq(&device[..3], numbered.as_bytes(), true)
+                // `¹`, `²` and `³` in utf-8
+                && matches!(device[3..], [b'0'..=b'9'] | [0xc2, 0xb2 | 0xb3 | 0xb9])
+        })
+}
+
+// check if the two are equal, ignoring the ascii case if asked
+fn eq(a: &[u8], b: &[u8], ignore_case: bool) -> bool {
+    if ignore_case {
+        a.eq_ignore_ascii_case(b)
+    } else {
+        a == b
+    }
+}
+
+// check if the name ends with the suffix, ignoring the ascii case if asked
+fn ends_with(name: &[u8], suf: &[u8], ignore_case: bool) -> bool {
+    name.len() >= suf.len() && eq(&name[name.len() - suf.len()..], suf, ignore_case)
+}
+
+// check if the name ends with the suf, see ListOption::add_suf,
+// a suf starting with an ascii letter or digit is the whole name or follows a `.`,
+// so that suf("rs") is not cut out of letters
+fn has_suf(name: &[u8], suf: &[u8], ignore_case: bool) -> bool {
+    if !ends_with(name, suf, ignore_case) {
+        return false;
+    }
+    let start = name.len() - suf.len();
+    !suf.first().is_some_and(u8::is_ascii_alphanumeric) || start == 0 || name[start - 1] == b'.'
+}
+
+// check if the needle is found anywhere in the haystack
+fn contains_bytes(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
+
+// the name of a listed path which the name filters see, so a filesystem root
+// like `/`, `C:\` or `\\server\share` has none, nor `.` or `..`, and is an unhidden one
+pub(crate) fn listed_name(path: &Path) -> &OsStr {
+    path.file_name().unwrap_or_default()
+}
+
+// push the string to the list unless it is already there
+pub(crate) fn push_unique(list: &mut Vec<String>, s: &str) {
+    if !list.iter().any(|item| item == s) {
+        list.push(s.to_string());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // check the file name of the path against the include filters
+    fn shows<P: AsRef<Path>>(
+        option: &ListOption,
+        path: P,
+        kind: EntryKind,
+        hidden_attr: Option<bool>,
+    ) -> bool {
+        let names = option.name_filters();
+        let name = names.fold_name(listed_name(path.as_ref()));
+        option.show_name(&names, &name, kind, hidden_attr)
+    }
+
+    #[test]
+    fn roots_have_no_name() {
+        for root in ["/", ".", "..", "dir/.."] {
+            assert_eq!(listed_name(Path::new(root)), "", "{}", root);
+        }
+        assert_eq!(listed_name(Path::new("dir/.hidden")), ".hidden");
+        let option = ListOption::default();
+        assert!(shows(&option, "/", EntryKind::Dir, Some(false)));
+    }
+
+    #[test]
+    fn windows_invalid_names() {
+        for name in [
+            "a<b",
+            "a:b",
+            "a\"b",
+            "a|b",
+            "a?b",
+            "a*b",
+            "a\\b",
+            "tab\t",
+            "dot.",
+            "space ",
+            "CON",
+            "con",
+            "nul.txt",
+            "Aux.tar.gz",
+            "nul .txt",
+            "COM1",
+            "lpt9.log",
+            "com¹",
+            "LPT³",
+        ] {
+            assert!(is_windows_invalid(name.as_bytes()), "{}", name);
+        }
+        for name in [
+            "a.txt", ".hidden", "console", "nul_", "COM", "COM10", "lpt0x", "com⁴", "a b", "con㊀",
+        ] {
+            assert!(!is_windows_invalid(name.as_bytes()), "{}", name);
+        }
+    }
+
+    #[test]
+    fn windows_names_under_the_root() {
+        let mut option = ListOption::default();
+        let root = Path::new("con");
+        assert!(option.show_windows_names(&root.join("a"), Some(Path::new("a"))));
+        option.only_windows_invalid_names(true);
+        // the root is only checked when it is the listed path
+        assert!(!option.show_windows_names(&root.join("a"), Some(Path::new("a"))));
+        assert!(option.show_windows_names(root, None));
+        let rel = Path::new("ok/aux/file.txt");
+        assert!(option.show_windows_names(&root.join(rel), Some(rel)));
+    }
+
+    // such names can't be made on windows
+    #[cfg(unix)]
+    #[test]
+    fn lists_windows_invalid_names() {
+        let dir = crate::test_dir::TestDir::new();
+        for path in ["ok.txt", "bad:name", "aux/inner.txt", "sub/trailing."] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .only_windows_invalid_names(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["aux", "aux/inner.txt", "bad:name", "sub/trailing."]
+        );
+    }
+
+    #[test]
+    fn prefixes_on_the_file_name() {
+        let mut option = ListOption::default();
+        option.prefix("backup_").ext("sql");
+        assert!(option.would_show_name("backup_2024.sql", EntryKind::File));
+        assert!(!option.would_show_name("backup_2024.txt", EntryKind::File));
+        assert!(!option.would_show_name("old_backup_2024.sql", EntryKind::File));
+        option.prefixes(["a", "b"]);
+        assert!(option.would_show_name("a.sql", EntryKind::File));
+        assert!(option.would_show_name("b.sql", EntryKind::File));
+        assert!(!option.would_show_name("backup_2024.txt", EntryKind::File));
+        assert!(!option.would_show_name("c.sql", EntryKind::File));
+        // the hidden rules still apply
+        option.prefixes(["."]);
+        assert!(!option.would_show_name(".env.sql", EntryKind::File));
+        option.hidden(true);
+        assert!(option.would_show_name(".env.sql", EntryKind::File));
+    }
+
+    #[test]
+    fn exact_names() {
+        let dir = crate::test_dir::TestDir::new();
+        for path in [
+            "Cargo.toml",
+            "MyCargo.toml",
+            "Cargo.toml.bak",
+            "a/Cargo.toml",
+            "b/Cargo.toml/inner",
+        ] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .name("Cargo.toml");
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["Cargo.toml", "a/Cargo.toml", "b/Cargo.toml"]
+        );
+        option.dir(false);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["Cargo.toml", "a/Cargo.toml"]
+        );
+        option.names(["MyCargo.toml", "inner"]);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["MyCargo.toml", "b/Cargo.toml/inner"]
+        );
+    }
+
+    #[test]
+    fn name_contains_searches_the_name_only() {
+        let mut option = ListOption::default();
+        option.name_contains("src");
+        assert!(option.would_show_name("my_src_dir", EntryKind::Dir));
+        assert!(option.would_show_name("src", EntryKind::Dir));
+        assert!(!option.would_show_name("main.rs", EntryKind::File));
+        assert!(!shows(&option, "src/main.rs", EntryKind::File, Some(false)));
+        option.name_contains("test");
+        assert!(option.would_show_name("test_a.rs", EntryKind::File));
+        assert!(!option.would_show_name("SRC", EntryKind::Dir));
+    }
+
+    #[test]
+    fn case_insensitive_exts_fold_ascii_only() {
+        let mut option = ListOption::default();
+        option.ext("jpg").suf("_Draft.md").exclude_ext("TMP");
+        assert!(!option.would_show_name("a.JPG", EntryKind::File));
+        option.case_insensitive_ext(true);
+        assert!(option.would_show_name("a.JPG", EntryKind::File));
+        assert!(option.would_show_name("b.jpg", EntryKind::File));
+        assert!(option.would_show_name("notes_draft.MD", EntryKind::File));
+        assert!(!option.would_show_name("a.tmp", EntryKind::File));
+        option.exts(["jpé"]);
+        assert!(option.would_show_name("a.JPé", EntryKind::File));
+        assert!(!option.would_show_name("a.JPÉ", EntryKind::File));
+        // the other name filters keep the case
+        option.clear_sufs().name("README");
+        assert!(!option.would_show_name("readme", EntryKind::File));
+    }
+
+    #[test]
+    fn case_insensitive_exts_when_listing() {
+        let dir = crate::test_dir::TestDir::new();
+        for name in ["a.JPG", "b.jpg", "c.jpeg"] {
+            dir.file(name, b"");
+        }
+        let mut option = ListOption::default();
+        option.include_root(false).ext("jpg");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["b.jpg"]);
+        option.case_insensitive_ext(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a.JPG", "b.jpg"]);
+    }
+
+    #[test]
+    fn has_extension_agrees_with_path_extension() {
+        for name in [
+            "main.rs", "main.ers", "letters", "rs", ".rs", "..rs", "a..rs", "archive.", ".", "..",
+            "a.rs.bak", "a.b.rs",
+        ] {
+            let expected = Path::new(name).extension() == Some(OsStr::new("rs"));
+            assert_eq!(
+                has_extension(name.as_bytes(), b"rs", false),
+                expected,
+                "{}",
+                name
+            );
+        }
+        let mut option = ListOption::default();
+        option.ext(".rs");
+        assert!(option.would_show_name("main.rs", EntryKind::File));
+        assert!(!option.would_show_name("letters", EntryKind::File));
+        assert!(!option.would_show_name("rs", EntryKind::File));
+    }
+
+    #[test]
+    fn compound_exts() {
+        assert!(has_extension(b"backup.tar.gz", b"tar.gz", false));
+        assert!(has_extension(b"backup.tar.gz", b"gz", false));
+        assert!(!has_extension(b"notes.gz", b"tar.gz", false));
+        assert!(!has_extension(b".tar.gz", b"tar.gz", false));
+        assert!(!has_extension(b"backuptar.gz", b"tar.gz", false));
+        assert!(has_extension(b"a.TAR.GZ", b"tar.gz", true));
+        let mut option = ListOption::default();
+        option.exts(["tar.gz", ".d.ts"]).exclude_ext("min.d.ts");
+        assert!(option.would_show_name("backup.tar.gz", EntryKind::File));
+        assert!(option.would_show_name("index.d.ts", EntryKind::File));
+        assert!(!option.would_show_name("index.min.d.ts", EntryKind::File));
+        assert!(!option.would_show_name("index.ts", EntryKind::File));
+    }
+
+    #[test]
+    fn no_extension() {
+        let mut option = ListOption::default();
+        option.no_extension(true).hidden(true);
+        assert!(option.would_show_name("Makefile", EntryKind::File));
+        assert!(option.would_show_name(".bashrc", EntryKind::File));
+        assert!(!option.would_show_name("archive.", EntryKind::File));
+        assert!(!option.would_show_name("main.rs", EntryKind::File));
+        assert!(option.would_show_name("src.d", EntryKind::Dir));
+        option.suf("_test");
+        assert!(option.would_show_name("run_test", EntryKind::File));
+        assert!(!option.would_show_name("run_test.rs", EntryKind::File));
+        assert!(!option.would_show_name("Makefile", EntryKind::File));
+        option.ext("rs");
+        assert!(option.validate().is_err());
+    }
+
+    #[test]
+    fn sufs_keep_directories_by_default() {
+        let dir = crate::test_dir::TestDir::new();
+        dir.file("main.rs", b"");
+        dir.file("notes.txt", b"");
+        dir.file("src/lib.rs", b"");
+        dir.file("config.rs/mod.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false).ext("rs");
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["config.rs", "main.rs", "src", "src/lib.rs"]
+        );
+        option.apply_sufs_to_dirs(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["config.rs", "main.rs", "src/lib.rs"]
+        );
+    }
+
+    #[test]
+    fn sufs_match_the_file_name_only() {
+        let dir = crate::test_dir::TestDir::new();
+        dir.file("conf/app.toml", b"");
+        dir.file("my.conf", b"");
+        dir.file("letters", b"");
+        dir.file("main.rs", b"");
+        dir.file("parsers/mod.txt", b"");
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .only_file()
+            .suf("conf");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["my.conf"]);
+        option.sufs(["rs"]);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["main.rs"]);
+        option.dir(true).apply_sufs_to_dirs(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["main.rs"]);
+        option.sufs(["/app.toml"]);
+        assert!(option.list_paths(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn sufs_starting_with_a_letter_or_digit_follow_a_dot() {
+        let mut option = ListOption::default();
+        option.sufs(["rs", "tar.gz", "~"]);
+        for name in ["main.rs", "rs", "backup.tar.gz", "notes.txt~"] {
+            assert!(option.would_show_name(name, EntryKind::File), "{name}");
+        }
+        for name in ["letters", "main.ers", "foo_tar.gz"] {
+            assert!(!option.would_show_name(name, EntryKind::File), "{name}");
+        }
+        option.sufs(["_test.rs"]);
+        assert!(option.would_show_name("main_test.rs", EntryKind::File));
+        option.clear_sufs().exclude_suf("bak");
+        assert!(!option.would_show_name("notes.bak", EntryKind::File));
+        assert!(option.would_show_name("kebak", EntryKind::File));
+    }
+
+    #[test]
+    fn stems_as_path_file_stem() {
+        for name in [
+            "mod.rs",
+            "a.tar.gz",
+            ".gitignore",
+            "Makefile",
+            "archive.",
+            "a..b",
+        ] {
+            let expected = Path::new(name).file_stem().unwrap().as_encoded_bytes();
+            assert_eq!(file_stem(name.as_bytes()), expected, "{}", name);
+        }
+        let mut option = ListOption::default();
+        option.stem("mod");
+        assert!(option.would_show_name("mod.rs", EntryKind::File));
+        assert!(option.would_show_name("mod.ts", EntryKind::File));
+        assert!(!option.would_show_name("mod.rs.bak", EntryKind::File));
+        assert!(!option.would_show_name("module.rs", EntryKind::File));
+        option.stems(["index"]).exts(["html", "htm"]);
+        assert!(option.would_show_name("index.htm", EntryKind::File));
+        assert!(!option.would_show_name("index.js", EntryKind::File));
+        assert!(!option.would_show_name("mod.html", EntryKind::File));
+    }
+
+    #[test]
+    fn case_insensitive_for_all_name_filters() {
+        let mut option = ListOption::default();
+        option.case_insensitive(true).name("readme.md");
+        assert!(option.would_show_name("README.MD", EntryKind::File));
+        option.names(["straße"]);
+        assert!(option.would_show_name("STRAßE", EntryKind::File));
+        option.names(["äpfel"]);
+        assert!(option.would_show_name("Äpfel", EntryKind::File));
+        let mut option = ListOption::default();
+        option
+            .case_insensitive(true)
+            .prefix("Test_")
+            .stem("A")
+            .name_contains("_b")
+            .ext("RS");
+        // the stem is the whole name before the ext
+        assert!(!option.would_show_name("test_a_B.rs", EntryKind::File));
+        option.stems(["test_a_B"]);
+        assert!(option.would_show_name("TEST_a_b.Rs", EntryKind::File));
+        assert!(!option.would_show_name("TEST_a_b.txt", EntryKind::File));
+
+        let dir = crate::test_dir::TestDir::new();
+        dir.file("target/a.rs", b"");
+        dir.file("src/b.rs", b"");
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .case_insensitive(true)
+            .exclude_dir("Target");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["src", "src/b.rs"]);
+    }
+
+    #[cfg(feature = "unicode")]
+    #[test]
+    fn normalized_unicode_names() {
+        let decomposed = "cafe\u{301}.txt";
+        let mut option = ListOption::default();
+        option.name("café.txt");
+        assert!(!option.would_show_name(decomposed, EntryKind::File));
+        option.normalize_unicode(true);
+        assert!(option.would_show_name(decomposed, EntryKind::File));
+        assert!(option.would_show_name("café.txt", EntryKind::File));
+        let mut option = ListOption::default();
+        option
+            .normalize_unicode(true)
+            .prefix("cafe\u{301}")
+            .ext("txt");
+        assert!(option.would_show_name("café.txt", EntryKind::File));
+        option.clear_sufs().exclude_suf("é.txt");
+        assert!(!option.would_show_name(decomposed, EntryKind::File));
+    }
+
+    #[test]
+    fn excluded_dirs_are_pruned() {
+        let dir = crate::test_dir::TestDir::new();
+        for path in [
+            "target/debug/a",
+            "src/target",
+            "src/node_modules/x/y.js",
+            "src/main.rs",
+        ] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .exclude_dir("target")
+            .exclude_dir("node_modules")
+            .prune_if(|path| path.ends_with("debug") && panic!("read under an excluded dir"));
+        // a file named target is still listed
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["src", "src/main.rs", "src/target"]
+        );
+        // the root itself is never excluded
+        let mut option = ListOption::default();
+        option.recursive(true).exclude_dir("target");
+        assert_eq!(option.count(dir.join("target")), 3);
+    }
+
+    #[test]
+    fn filters_are_folded_once_whatever_the_order() {
+        let mut option = ListOption::default();
+        option.names(["README.md"]).exclude_dir("Target");
+        assert_eq!(option.name_filters().names, [b"README.md"]);
+        option.case_insensitive(true).ext("JPG");
+        let names = option.name_filters();
+        assert_eq!(names.names, [b"readme.md"]);
+        assert_eq!(names.exclude_dirs, [b"target"]);
+        assert_eq!(names.exts, [b"jpg"]);
+        // only the entry name is folded then, the filters are kept as set
+        assert_eq!(
+            *names.fold_name(OsStr::new("ReadMe.MD")).folded,
+            *b"readme.md"
+        );
+        assert_eq!(option.extensions(), ["JPG"]);
+    }
+
+    // the server and the share are the prefix of a UNC path on windows, with no name of their
+    // own, while elsewhere such a path is a single name with backslashes
+    #[test]
+    fn unc_roots_have_no_name() {
+        for root in [
+            r"\\server\share",
+            r"\\server\share\",
+            r"\\?\UNC\server\share",
+        ] {
+            let expected = if cfg!(windows) { "" } else { root };
+            assert_eq!(listed_name(Path::new(root)), expected, "{}", root);
+        }
+        let name = listed_name(Path::new(r"\\server\share\dir"));
+        assert_eq!(name == "dir", cfg!(windows));
+        // a root is shown whatever the hidden filters
+        let mut option = ListOption::default();
+        option.only_hidden();
+        assert!(shows(&option, r"\\server\share", EntryKind::Dir, None));
+        assert!(shows(&option, r"\\server\share\.dir", EntryKind::Dir, None));
+    }
+
+    #[test]
+    fn unc_entries_are_checked_by_their_own_name() {
+        let mut option = ListOption::default();
+        option.ext("rs");
+        let file = r"\\server\share\dir\main.rs";
+        assert!(shows(&option, file, EntryKind::File, Some(false)));
+        let other = r"\\server\share\dir\main.txt";
+        assert!(!shows(&option, other, EntryKind::File, Some(false)));
+        let hidden = r"\\server\share\dir\.main.rs";
+        assert_eq!(
+            shows(&option, hidden, EntryKind::File, Some(false)),
+            !cfg!(windows)
+        );
+        option.name("main.rs");
+        assert_eq!(
+            shows(&option, file, EntryKind::File, Some(false)),
+            cfg!(windows)
+        );
+    }
+
+    #[test]
+    fn unc_prefixes_are_not_checked_as_windows_names() {
+        let mut option = ListOption::default();
+        option.only_windows_invalid_names(true);
+        let root = Path::new(r"\\server\share");
+        assert_eq!(option.show_windows_names(root, None), !cfg!(windows));
+        let con = Path::new(r"\\server\share\con");
+        assert!(option.show_windows_names(con, None));
+        // the names under the root only, as the listing gives them
+        let path = Path::new(r"\\server\share\dir\sub\a.rs");
+        assert_eq!(
+            option.show_windows_names(path, Some(Path::new(r"sub\a.rs"))),
+            !cfg!(windows)
+        );
+        let path = Path::new(r"\\server\share\dir\nul.txt");
+        assert!(option.show_windows_names(path, Some(Path::new("nul.txt"))));
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn verbatim_roots_keep_their_prefix() {
+        let dir = crate::test_dir::TestDir::new();
+        dir.file(r"sub\a.rs", b"");
+        let root = std::path::PathBuf::from(format!(r"\\?\{}", dir.path().display()));
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true).include_root(false);
+        assert_eq!(
+            option.list_paths(&root),
+            [root.join("sub"), root.join(r"sub\a.rs")]
+        );
+        let entries = option.ext("rs").only_file().entries(&root);
+        assert_eq!(entries[0].path(), root.join(r"sub\a.rs"));
+    }
+}
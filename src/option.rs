@@ -1,21 +1,252 @@
-use std::{ffi::OsStr, path::Path};
+#[cfg(feature = "glob")]
+use crate::Globs;
+#[cfg(feature = "regex")]
+use crate::Pattern;
+use crate::{
+    error::is_dangling,
+    expect_listed,
+    interrupt::CancelToken,
+    metadata::ListStart,
+    name::{listed_name, push_unique, NameFilters},
+    precedence::resolve,
+    predicate::Predicate,
+    ConfigError, Entry, EntryKind, ErrorPolicy, ListError, ListIter, TryListIter, Walker,
+};
+use std::{
+    collections::HashSet,
+    ffi::OsStr,
+    fs::{self, Metadata},
+    ops::ControlFlow,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime},
+};
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub struct ListOption {
     // if true, list directories
-    dir: bool,
+    pub(crate) dir: bool,
     // if true, list files
-    file: bool,
+    pub(crate) file: bool,
+    // if true, show symlinks
+    pub(crate) symlink: bool,
+    // if true, show only symlinks
+    pub(crate) only_symlinks: bool,
+    // default false, if true, show the symlinks pointing to nothing
+    pub(crate) broken_symlinks: bool,
+    // default true, if true, filter the symlinks by their targets and descend into the directory ones
+    pub(crate) follow_symlinks: bool,
+    // default false, if true, show the named pipes
+    pub(crate) fifos: bool,
+    // default false, if true, show the unix domain sockets
+    pub(crate) sockets: bool,
+    // default false, if true, show the block and character devices
+    pub(crate) devices: bool,
     // if true, show hidden files
-    hidden: bool,
+    pub(crate) hidden: bool,
     // if true,show unhidden files
-    unhidden: bool,
-    // if true, list recursively
-    recursive: bool,
-    // default 1, list only current directory
-    level: usize,
-    // if not empty, list only files with these extensions
-    sufs: Vec<String>,
+    pub(crate) unhidden: bool,
+    // default false, if true, descend into the hidden directories even if they are not shown
+    pub(crate) descend_hidden: bool,
+    // if true, list recursively, without limit unless the level is set
+    pub(crate) recursive: bool,
+    // the levels listed under the root, default 1 if not set, list only current directory
+    pub(crate) level: Option<usize>,
+    // default 0, list only entries at least this deep, the root having depth 0
+    pub(crate) min_depth: usize,
+    // default false, if true, list the entries level by level instead of depth first
+    pub(crate) breadth_first: bool,
+    // default false, if true, list a directory after the entries under it
+    pub(crate) contents_first: bool,
+    // default false, if true, read the entries of each directory sorted by name
+    pub(crate) sorted: bool,
+    // if set, read the directories of each directory first if true, or its files first if false
+    pub(crate) dirs_first: Option<bool>,
+    // default false, if true, never descend into a directory on another device than the root
+    pub(crate) same_file_system: bool,
+    // default false, if true, never descend into a directory on a pseudo filesystem, on linux
+    pub(crate) skip_pseudo_fs: bool,
+    // default false, if true, list the bundle directories as files, without descending into them
+    pub(crate) bundles_as_files: bool,
+    // the exts of the bundle directories, default the macos ones like app
+    pub(crate) bundle_exts: Vec<String>,
+    // if set, stop listing after this many entries
+    pub(crate) max_results: Option<usize>,
+    // if set, read at most this many entries of each directory
+    pub(crate) max_entries_per_dir: Option<usize>,
+    // if set, stop listing once this is set
+    pub(crate) cancel: Option<CancelToken>,
+    // if set, stop listing before reading a directory once this time has passed
+    pub(crate) timeout: Option<Duration>,
+    // if exts or sufs is not empty, list only files with one of these extensions, without the leading `.`
+    pub(crate) exts: Vec<String>,
+    // if exts or sufs is not empty, list only files whose name ends with one of these
+    pub(crate) sufs: Vec<String>,
+    // never list files with these extensions, even if they match exts or sufs
+    pub(crate) exclude_exts: Vec<String>,
+    // never list files whose name ends with one of these, even if they match exts or sufs
+    pub(crate) exclude_sufs: Vec<String>,
+    // never list nor descend into directories with these names
+    pub(crate) exclude_dirs: Vec<String>,
+    // never list nor descend into these paths, relative ones are relative to the root
+    pub(crate) exclude_paths: Vec<PathBuf>,
+    // default false, if true, list only entries without extension
+    pub(crate) no_extension: bool,
+    // default false, if true, exts, sufs and no_extension also filter directories
+    pub(crate) sufs_on_dirs: bool,
+    // default false, if true, match exts and sufs ignoring the ascii case
+    pub(crate) ext_ignore_case: bool,
+    // default false, if true, all the name filters ignore the case
+    pub(crate) ignore_case: bool,
+    // if not empty, list only entries whose file name starts with one of these
+    pub(crate) prefixes: Vec<String>,
+    // if not empty, list only entries whose file name is one of these
+    pub(crate) names: Vec<String>,
+    // if not empty, list only entries whose file stem is one of these
+    pub(crate) stems: Vec<String>,
+    // if not empty, list only entries whose file name contains one of these
+    pub(crate) name_contains: Vec<String>,
+    // if not empty, list only entries whose path relative to the root matches one of these
+    #[cfg(feature = "glob")]
+    pub(crate) globs: Globs,
+    // list the entries matching these even if excluded
+    #[cfg(feature = "glob")]
+    pub(crate) reincludes: Globs,
+    // if set, list only entries whose file name matches it
+    #[cfg(feature = "regex")]
+    pub(crate) name_regex: Option<Pattern>,
+    // if set, list only entries whose path matches it
+    #[cfg(feature = "regex")]
+    pub(crate) path_regex: Option<Pattern>,
+    // default false, if true, names and the name filters are compared in the unicode NFC form
+    #[cfg(feature = "unicode")]
+    pub(crate) normalize_unicode: bool,
+    // default false, if true, skip the entries ignored by .gitignore files
+    #[cfg(feature = "ignore")]
+    pub(crate) respect_gitignore: bool,
+    // skip the entries ignored by the files with these names, in the gitignore syntax
+    #[cfg(feature = "ignore")]
+    pub(crate) ignore_filenames: Vec<String>,
+    // if set, list only files at least this large, in bytes
+    pub(crate) min_size: Option<u64>,
+    // if set, list only files at most this large, in bytes
+    pub(crate) max_size: Option<u64>,
+    // default false, if true, list only the empty files
+    pub(crate) only_empty: bool,
+    // default false, if true, never list the empty files
+    pub(crate) skip_empty: bool,
+    // default false, if true, list only the directories without any entry
+    pub(crate) only_empty_dirs: bool,
+    // default false, if true, never list a directory read without listing any entry under it
+    pub(crate) prune_empty_dirs: bool,
+    // default false, if true, a directory is only listed when some non-directory entry under it is
+    pub(crate) dirs_with_matches_only: bool,
+    // if set, list only entries whose path has at least this length
+    pub(crate) min_path_len: Option<usize>,
+    // if set, list only entries whose path has at most this length
+    pub(crate) max_path_len: Option<usize>,
+    // default false, if true, list only entries whose path has a name invalid on windows
+    pub(crate) only_windows_invalid: bool,
+    // if not empty, list only entries with all these extended attributes, with these values if set
+    #[cfg(all(unix, feature = "xattr"))]
+    pub(crate) xattrs: Vec<(String, Option<Vec<u8>>)>,
+    // if set, list only the entries tracked by git if true, or only the untracked ones if false
+    #[cfg(feature = "git")]
+    pub(crate) git_tracked: Option<bool>,
+    // if set, list only entries modified at this time or later
+    pub(crate) modified_after: Option<SystemTime>,
+    // if set, list only entries modified at this time or earlier
+    pub(crate) modified_before: Option<SystemTime>,
+    // if set, list only entries modified at least this long before the listing started
+    pub(crate) older_than: Option<Duration>,
+    // if set, list only entries modified at most this long before the listing started
+    pub(crate) newer_than: Option<Duration>,
+    // if set, list only entries accessed at this time or later
+    pub(crate) accessed_after: Option<SystemTime>,
+    // if set, list only entries accessed at this time or earlier
+    pub(crate) accessed_before: Option<SystemTime>,
+    // if set, list only entries created at this time or later
+    pub(crate) created_after: Option<SystemTime>,
+    // if set, list only entries created at this time or earlier
+    pub(crate) created_before: Option<SystemTime>,
+    // if set, list only entries modified after the file at this path
+    pub(crate) newer_than_path: Option<PathBuf>,
+    // if set, list only entries with some of these permission bits
+    #[cfg(unix)]
+    pub(crate) mode_any: Option<u32>,
+    // if set, list only entries with all of these permission bits
+    #[cfg(unix)]
+    pub(crate) mode_all: Option<u32>,
+    // if set, list only entries with exactly these permission bits
+    #[cfg(unix)]
+    pub(crate) mode_exact: Option<u32>,
+    // if set, list only entries owned by this user id
+    #[cfg(unix)]
+    pub(crate) uid: Option<u32>,
+    // if set, list only entries owned by this group id
+    #[cfg(unix)]
+    pub(crate) gid: Option<u32>,
+    // default false, if true, list only entries owned by another user than the current one
+    #[cfg(unix)]
+    pub(crate) not_owned_by_me: bool,
+    // if set, list only files with at least this many hardlinks
+    #[cfg(unix)]
+    pub(crate) min_links: Option<u64>,
+    // if set, list only files with at most this many hardlinks
+    #[cfg(unix)]
+    pub(crate) max_links: Option<u64>,
+    // default false, if true, list only the sparse files
+    #[cfg(unix)]
+    pub(crate) only_sparse: bool,
+    // the bytes a file must allocate less than its size to be sparse
+    #[cfg(unix)]
+    pub(crate) sparse_threshold: u64,
+    // default false, if true, list only the executable files
+    pub(crate) only_executable: bool,
+    // the exts of the executable files where there is no execute bit, without the leading dot
+    pub(crate) executable_exts: Vec<String>,
+    // default false, if true, list only entries the process can read
+    pub(crate) readable: bool,
+    // default false, if true, list only entries the process can write
+    pub(crate) writable: bool,
+    // default false, if true, list only the read-only files
+    pub(crate) only_readonly: bool,
+    // default false, if true, never list the read-only files
+    pub(crate) skip_readonly: bool,
+    // default false, if true, only_readonly and skip_readonly also filter directories
+    pub(crate) readonly_on_dirs: bool,
+    // default true, if true, the entries with the hidden attribute are hidden on windows
+    pub(crate) hidden_by_attribute: bool,
+    // default false, if true, the UF_HIDDEN flag doesn't hide the entries on macos
+    pub(crate) ignore_finder_flags: bool,
+    // list only entries with all of these file attributes
+    #[cfg(windows)]
+    pub(crate) with_attributes: u32,
+    // list only entries with none of these file attributes
+    #[cfg(windows)]
+    pub(crate) without_attributes: u32,
+    // if not empty, list only files whose detected MIME type is one of these
+    #[cfg(feature = "infer")]
+    pub(crate) content_types: Vec<String>,
+    // default false, if true, list only the files which look like text
+    pub(crate) only_text: bool,
+    // default false, if true, list only the files which don't look like text
+    pub(crate) only_binary: bool,
+    // the bytes read from the start of a file to tell text from binary
+    pub(crate) sample_len: u64,
+    // if set, list only files containing these bytes
+    pub(crate) content_pattern: Option<Vec<u8>>,
+    // if set, search at most these bytes of each file for the content pattern
+    pub(crate) max_scan_bytes: Option<u64>,
+    // default false, if true, list only the first path of a file with several hardlinks
+    pub(crate) dedup_hardlinks: bool,
+    // list only entries passing all of these
+    pub(crate) filters: Vec<Predicate>,
+    // never list nor descend into directories for which one of these is true
+    pub(crate) prune_fns: Vec<Predicate>,
+    // default true, if false, never list the root path itself
+    pub(crate) include_root: bool,
+    // default Abort, what to do when some entry can't be read
+    pub(crate) error_policy: ErrorPolicy,
 }
 // Default implementation for ListOption
 impl Default for ListOption {
@@ -23,11 +254,138 @@ impl Default for ListOption {
         Self {
             dir: true,
             file: true,
+            symlink: true,
+            only_symlinks: false,
+            broken_symlinks: false,
+            follow_symlinks: true,
+            fifos: false,
+            sockets: false,
+            devices: false,
             hidden: false,
             unhidden: true,
+            descend_hidden: false,
             recursive: false,
-            level: 1,
+            level: None,
+            min_depth: 0,
+            breadth_first: false,
+            contents_first: false,
+            sorted: false,
+            dirs_first: None,
+            same_file_system: false,
+            skip_pseudo_fs: false,
+            bundles_as_files: false,
+            bundle_exts: [
+                "app",
+                "bundle",
+                "framework",
+                "photoslibrary",
+                "plugin",
+                "kext",
+            ]
+            .map(String::from)
+            .to_vec(),
+            max_results: None,
+            max_entries_per_dir: None,
+            cancel: None,
+            timeout: None,
+            exts: Vec::new(),
             sufs: Vec::new(),
+            exclude_exts: Vec::new(),
+            exclude_sufs: Vec::new(),
+            exclude_dirs: Vec::new(),
+            exclude_paths: Vec::new(),
+            no_extension: false,
+            sufs_on_dirs: false,
+            ext_ignore_case: false,
+            ignore_case: false,
+            prefixes: Vec::new(),
+            names: Vec::new(),
+            stems: Vec::new(),
+            name_contains: Vec::new(),
+            #[cfg(feature = "glob")]
+            globs: Globs::default(),
+            #[cfg(feature = "glob")]
+            reincludes: Globs::default(),
+            #[cfg(feature = "regex")]
+            name_regex: None,
+            #[cfg(feature = "regex")]
+            path_regex: None,
+            #[cfg(feature = "unicode")]
+            normalize_unicode: false,
+            #[cfg(feature = "ignore")]
+            respect_gitignore: false,
+            #[cfg(feature = "ignore")]
+            ignore_filenames: Vec::new(),
+            min_size: None,
+            max_size: None,
+            only_empty: false,
+            skip_empty: false,
+            only_empty_dirs: false,
+            prune_empty_dirs: false,
+            dirs_with_matches_only: false,
+            min_path_len: None,
+            max_path_len: None,
+            only_windows_invalid: false,
+            #[cfg(all(unix, feature = "xattr"))]
+            xattrs: Vec::new(),
+            #[cfg(feature = "git")]
+            git_tracked: None,
+            modified_after: None,
+            modified_before: None,
+            older_than: None,
+            newer_than: None,
+            accessed_after: None,
+            accessed_before: None,
+            created_after: None,
+            created_before: None,
+            newer_than_path: None,
+            #[cfg(unix)]
+            mode_any: None,
+            #[cfg(unix)]
+            mode_all: None,
+            #[cfg(unix)]
+            mode_exact: None,
+            #[cfg(unix)]
+            uid: None,
+            #[cfg(unix)]
+            gid: None,
+            #[cfg(unix)]
+            not_owned_by_me: false,
+            #[cfg(unix)]
+            min_links: None,
+            #[cfg(unix)]
+            max_links: None,
+            #[cfg(unix)]
+            only_sparse: false,
+            #[cfg(unix)]
+            sparse_threshold: 4096,
+            only_executable: false,
+            readable: false,
+            writable: false,
+            only_readonly: false,
+            skip_readonly: false,
+            readonly_on_dirs: false,
+            hidden_by_attribute: true,
+            ignore_finder_flags: false,
+            #[cfg(windows)]
+            with_attributes: 0,
+            #[cfg(windows)]
+            without_attributes: 0,
+            executable_exts: ["exe", "bat", "cmd", "com", "ps1"]
+                .map(String::from)
+                .to_vec(),
+            #[cfg(feature = "infer")]
+            content_types: Vec::new(),
+            only_text: false,
+            only_binary: false,
+            sample_len: 8192,
+            content_pattern: None,
+            max_scan_bytes: None,
+            dedup_hardlinks: false,
+            filters: Vec::new(),
+            prune_fns: Vec::new(),
+            include_root: true,
+            error_policy: ErrorPolicy::default(),
         }
     }
 }
@@ -37,6 +395,13 @@ impl ListOption {
     pub fn new() -> Self {
         Self::default()
     }
+
+    /// get an owned copy of the configured option, to end a chain of builder calls
+    ///
+    /// e.g. `let option = ListOption::new().recursive(true).ext("rs").build();`
+    pub fn build(&mut self) -> Self {
+        self.clone()
+    }
     /// set if allow this option to show directories
     pub fn dir(&mut self, if_show: bool) -> &mut Self {
         self.dir = if_show;
@@ -49,7 +414,88 @@ impl ListOption {
         self
     }
 
+    /// set if allow this option to show symlinks, default true
+    ///
+    /// a symlink is listed by what it points to, so with file(false), a symlink to a file
+    /// isn't listed either, unless with follow_symlinks(false), while symlink(false) hides all the symlinks,
+    /// see [`ListOption::only_symlinks`] to list nothing else
+    pub fn symlink(&mut self, if_show: bool) -> &mut Self {
+        self.symlink = if_show;
+        self
+    }
+
+    /// set if the broken symlinks are shown, default false
+    ///
+    /// by default, a symlink to nothing, or looping onto itself like `ln -s loop loop`,
+    /// is silently skipped, as there is nothing to list,
+    /// while with this, it is listed as `EntryKind::Symlink`, filtered by its own name
+    /// and metadata, e.g. only_symlinks(true).include_broken_symlinks(true) finds all the symlinks
+    pub fn include_broken_symlinks(&mut self, if_include: bool) -> &mut Self {
+        self.broken_symlinks = if_include;
+        self
+    }
+
+    /// set if the symlinks are followed, default true
+    ///
+    /// when true, a symlink is filtered by the kind and metadata of its target,
+    /// and a symlink to a directory is descended into,
+    /// when false, a symlink is listed as `EntryKind::Symlink`, filtered by its own
+    /// metadata, broken or not, and never descended into
+    ///
+    /// either way, the name filters see the name of the symlink, not the one of its target,
+    /// so a link named `.secret` is hidden and a link named current.log matches ext("log")
+    ///
+    /// the root is followed either way, as it is the path asked for, and a symlink
+    /// to a directory above it, e.g. `ln -s .. loop`, is listed but not descended into
+    ///
+    /// on windows, a junction is a symlink too, as for `FileType::is_symlink`,
+    /// so a junction cycle ends the same way, see [`Entry::is_junction`] to tell it
+    pub fn follow_symlinks(&mut self, if_follow: bool) -> &mut Self {
+        self.follow_symlinks = if_follow;
+        self
+    }
+
+    /// set if the special files are shown, default false, only on unix
+    ///
+    /// they are the named pipes, the sockets and the devices, which can then be filtered
+    /// by name like files, see [`ListOption::fifos`], [`ListOption::sockets`]
+    /// and [`ListOption::devices`] to pick some of them
+    ///
+    /// they are never opened, so listing e.g. /dev doesn't block on a device
+    #[cfg(unix)]
+    pub fn special_files(&mut self, if_show: bool) -> &mut Self {
+        self.fifos = if_show;
+        self.sockets = if_show;
+        self.devices = if_show;
+        self
+    }
+
+    /// set if the named pipes are shown, default false, only on unix
+    #[cfg(unix)]
+    pub fn fifos(&mut self, if_show: bool) -> &mut Self {
+        self.fifos = if_show;
+        self
+    }
+
+    /// set if the unix domain sockets are shown, default false, only on unix
+    #[cfg(unix)]
+    pub fn sockets(&mut self, if_show: bool) -> &mut Self {
+        self.sockets = if_show;
+        self
+    }
+
+    /// set if the block and character devices are shown, default false, only on unix
+    #[cfg(unix)]
+    pub fn devices(&mut self, if_show: bool) -> &mut Self {
+        self.devices = if_show;
+        self
+    }
+
     /// set if allow this option to show hidden files
+    ///
+    /// with hidden(false), the hidden directories are not descended into either,
+    /// so nothing under `.git` is listed, see [`ListOption::descend_hidden`],
+    /// but a hidden root given to list is always traversed and shown by include_root
     pub fn hidden(&mut self, if_show: bool) -> &mut Self {
         self.hidden = if_show;
         self
@@ -61,78 +507,329 @@ impl ListOption {
         self
     }
 
-    /// set the level of recursion while listing files in some path
+    /// set if the hidden directories are descended into even with hidden(false), default false,
+    /// e.g. to list the unhidden entries under `.config` without `.config` itself
+    pub fn descend_hidden(&mut self, if_descend: bool) -> &mut Self {
+        self.descend_hidden = if_descend;
+        self
+    }
+
+    /// set the level of recursion while listing files in some path, default 1
+    ///
+    /// level(n) lists the entries at most n levels under the root, whether recursive or not,
+    /// e.g. level(1) lists the entries of the root, level(2) those of its directories too,
+    /// and no directory at the last level is read
+    ///
+    /// level(0) lists nothing, which [`ListOption::validate`] reports as `ConfigError::ZeroLevel`,
+    /// see [`ListOption::unlimited_depth`] for no limit
     pub fn level(&mut self, level: usize) -> &mut Self {
-        self.level = level;
+        self.level = Some(level);
+        self
+    }
+
+    /// list only entries at least this deep, default 0, while still descending through the others
+    ///
+    /// the root has depth 0 and the entries of level n have depth n, see [`ListOption::level`],
+    /// e.g. min_depth(2).level(3) lists the entries 2 or 3 levels under the root
+    pub fn min_depth(&mut self, depth: usize) -> &mut Self {
+        self.min_depth = depth;
         self
     }
 
     /// set if allow this option to list recursively
+    ///
+    /// without [`ListOption::level`], the whole tree is listed,
+    /// otherwise the level is still the max depth, e.g. recursive(true).level(2) lists two levels
     pub fn recursive(&mut self, if_choose: bool) -> &mut Self {
         self.recursive = if_choose;
         self
     }
 
-    /// add one ext to the list of allowed extensions
+    /// set if the entries are listed level by level, default false, i.e. depth first
+    ///
+    /// all the entries of level 1 are listed, then the ones of level 2, and so on,
+    /// so e.g. find_first finds the shallowest match without reading deeper directories,
+    /// the entries of a directory still come in the order they are read
     ///
-    /// only files with one of these extensions will be listed
+    /// prune_empty_dirs and dirs_with_matches_only need a directory to be read right after
+    /// it is visited, so they are ignored with this
+    pub fn breadth_first(&mut self, if_breadth_first: bool) -> &mut Self {
+        self.breadth_first = if_breadth_first;
+        self
+    }
+
+    /// set if a directory is listed after all the entries under it, like `find -depth`,
+    /// default false
     ///
-    /// e.g. ext("rs") will allow files with .rs extension to be listed
+    /// e.g. removing the listed paths in order never meets a directory which is not empty,
+    /// and the root, if listed, comes last, while the entries of a directory still come
+    /// in the order they are read, this only puts the parents after their children
     ///
-    /// e.g. ext("rs").ext("toml") will allow files with .rs and .toml extensions to be listed
-    pub fn ext(&mut self, ext: &str) -> &mut Self {
-        self.sufs.push(format!(".{}", ext));
+    /// it is ignored with breadth_first
+    pub fn contents_first(&mut self, if_contents_first: bool) -> &mut Self {
+        self.contents_first = if_contents_first;
         self
     }
 
-    /// add multiple exts to the list of allowed extensions
+    /// set if the entries of each directory are sorted by name, byte by byte, default false,
+    /// i.e. in the order of the filesystem, which differs between platforms and runs
     ///
-    /// only files with one of these extensions will be listed
+    /// each directory is then read whole before its first entry is listed,
+    /// and the order of the tree follows, e.g. depth first, breadth_first or contents_first
+    pub fn sorted(&mut self, if_sorted: bool) -> &mut Self {
+        self.sorted = if_sorted;
+        self
+    }
+
+    /// set if the directories in each directory come before the other entries, default false,
+    /// like in a file manager, replacing [`ListOption::files_first`]
     ///
-    /// but this function will shadow the previous sufs
+    /// each directory is then read whole before its first entry is listed, and with
+    /// [`ListOption::sorted`], the directories are sorted among themselves, then the files,
+    /// a symlink to a directory being a directory unless with follow_symlinks(false)
+    pub fn dirs_first(&mut self, if_first: bool) -> &mut Self {
+        if if_first || self.dirs_first == Some(true) {
+            self.dirs_first = if_first.then_some(true);
+        }
+        self
+    }
+
+    /// set if the directories in each directory come after the other entries, default false,
+    /// replacing [`ListOption::dirs_first`]
+    pub fn files_first(&mut self, if_first: bool) -> &mut Self {
+        if if_first || self.dirs_first == Some(false) {
+            self.dirs_first = if_first.then_some(false);
+        }
+        self
+    }
+
+    /// set if the directories on another filesystem than the root are not descended into,
+    /// like `find -xdev`, default false
     ///
-    /// e.g. exts(vec!["rs", "toml"]) will allow files with .rs and .toml extensions to be listed
+    /// the device is the one of the metadata on unix and the volume on windows,
+    /// and a mount point is still listed itself, e.g. /proc when listing /
+    pub fn same_file_system(&mut self, if_same: bool) -> &mut Self {
+        self.same_file_system = if_same;
+        self
+    }
+
+    /// stop listing after this many entries, whatever the api, e.g. for a preview
     ///
-    /// e.g. exts(vec!["rs"]).exts(vec!["toml"]) will only allow files with .toml extensions to be listed
+    /// no directory is read once the entries are found, and with [`ListOption::sorted`],
+    /// the same entries are found on every run, while the errors are not counted
+    pub fn max_results(&mut self, max: usize) -> &mut Self {
+        self.max_results = Some(max);
+        self
+    }
+
+    /// read at most this many entries of each directory, shown or not, then go on with the rest
+    /// of the tree, e.g. to get over a directory with millions of files
     ///
-    pub fn exts(&mut self, exts: Vec<&str>) -> &mut Self {
-        self.sufs = exts.iter().map(|s| format!(".{}", s)).collect();
+    /// the rest of such a directory is silently skipped, see [`TryListIter::truncated_dirs`]
+    /// to tell which ones were cut, and with [`ListOption::sorted`],
+    /// the directory is still read whole to be sorted, then cut
+    pub fn max_entries_per_dir(&mut self, max: usize) -> &mut Self {
+        self.max_entries_per_dir = Some(max);
         self
     }
 
-    /// add one suf to the list of allowed suffixes,
-    /// only files with one of these suffixes will be listed
+    /// set if the bundle directories, like Foo.app on macos, are listed as single files,
+    /// default false
     ///
-    /// e.g. suf("rs") will allow files with rs suffix to be listed
+    /// a directory whose name has one of the bundle exts, in any case, is then never descended
+    /// into, and it is a file for the kind and name filters, e.g. file(true).dir(false) lists it,
+    /// see [`ListOption::bundle_exts`] for the exts, while a bundle as the root is still read
+    pub fn treat_bundles_as_files(&mut self, if_treat: bool) -> &mut Self {
+        self.bundles_as_files = if_treat;
+        self
+    }
+
+    /// replace the exts of the bundle directories, default app, bundle, framework, photoslibrary,
+    /// plugin and kext, see [`ListOption::treat_bundles_as_files`]
+    pub fn bundle_exts<I, S>(&mut self, exts: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.bundle_exts.clear();
+        for ext in exts {
+            push_unique(&mut self.bundle_exts, normalize_ext(ext.as_ref()));
+        }
+        self
+    }
+
+    /// list the whole tree whatever the level set before, same as recursive(true) without a level
+    pub fn unlimited_depth(&mut self) -> &mut Self {
+        self.recursive = true;
+        self.level = None;
+        self
+    }
+
+    /// set if allow this option to list the root path itself, i.e. the path given to list
     ///
-    /// notice that exts function will shadow the previous sufs
+    /// if false, only the entries under the root are listed, so a file root lists nothing,
+    /// while the hidden and unhidden filters never drop the root
+    pub fn include_root(&mut self, if_include: bool) -> &mut Self {
+        self.include_root = if_include;
+        self
+    }
+
+    /// add one ext to the list of allowed extensions, same as [`ListOption::add_ext`]
     ///
-    /// e.g. suf(".rs").ext("toml") will only allow files with .rs and .toml extensions to be listed
+    /// e.g. ext("rs").ext("toml") will allow files with .rs and .toml extensions to be listed
+    pub fn ext(&mut self, ext: &str) -> &mut Self {
+        self.add_ext(ext)
+    }
+
+    /// replace the allowed suffixes with these exts, same as [`ListOption::set_exts`]
     ///
-    /// e.g. suf(".rs").suf(".toml") will only allow files with .toml extensions to be listed
+    /// e.g. exts(vec!["rs"]).exts(vec!["toml"]) will only allow files with .toml extension to be listed
+    pub fn exts<I, S>(&mut self, exts: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.set_exts(exts)
+    }
+
+    /// add one suf to the list of allowed suffixes, same as [`ListOption::add_suf`]
     ///
-    /// e.g. suf("rs").exts(vec!["toml"]) will only allow files with .toml extensions to be listed
+    /// e.g. suf(".rs").suf(".toml") will allow files with .rs and .toml suffixes to be listed
     pub fn suf(&mut self, suf: &str) -> &mut Self {
-        self.sufs.push(suf.to_string());
+        self.add_suf(suf)
+    }
+
+    /// replace the allowed suffixes with these sufs, same as [`ListOption::set_sufs`]
+    ///
+    /// e.g. sufs(vec![".rs"]).sufs(vec![".toml"]) will only allow files with .toml suffix to be listed
+    pub fn sufs<I, S>(&mut self, sufs: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.set_sufs(sufs)
+    }
+
+    /// append one ext to the allowed exts, keeping the exts and sufs added before
+    ///
+    /// only files with one of the allowed exts or sufs will be listed
+    ///
+    /// an ext is compared with the extension of the file name, as `Path::extension` sees it,
+    /// e.g. add_ext("rs") allows main.rs, but neither main.ers, letters, nor a file named rs
+    ///
+    /// a compound ext is compared with as many dotted parts at the end of the name,
+    /// e.g. add_ext("tar.gz") allows backup.tar.gz but not notes.gz
+    ///
+    /// the leading `.` is optional, e.g. add_ext("rs") and add_ext(".rs") are the same
+    pub fn add_ext(&mut self, ext: &str) -> &mut Self {
+        push_unique(&mut self.exts, normalize_ext(ext));
         self
     }
 
-    /// add multiple sufs to the list of allowed suffixes
+    /// append one suf to the allowed sufs, keeping the exts and sufs added before
     ///
-    /// only files with one of these suffixes will be listed
+    /// only files with one of the allowed exts or sufs will be listed
     ///
-    /// but this function will shadow the previous sufs
+    /// unlike an ext, a suf is matched as it is against the end of the file name,
+    /// e.g. add_suf("_test.rs") allows main_test.rs and add_suf("~") allows notes.txt~,
+    /// but a suf starting with an ascii letter or digit has to be the whole name or follow a `.`,
+    /// e.g. add_suf("rs") allows main.rs and rs, but neither letters nor a directory named parsers
     ///
-    /// e.g. sufs(vec!["rs", "toml"]) will allow files with rs and toml suffixes to be listed
+    /// only the file name is matched, never the directories above it,
+    /// e.g. add_suf("conf") doesn't allow conf/app.toml
+    pub fn add_suf(&mut self, suf: &str) -> &mut Self {
+        push_unique(&mut self.sufs, suf);
+        self
+    }
+
+    /// replace the allowed exts and sufs with these exts, dropping the ones added before
     ///
-    /// e.g. sufs(vec![".rs"]).sufs(vec![".toml"]) will only allow files with .toml extensions to be listed
+    /// any iterable of strings works, e.g. `["rs", "toml"]`, `vec![String::from("rs")]` or an iterator chain
+    pub fn set_exts<I, S>(&mut self, exts: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.clear_sufs();
+        for ext in exts {
+            self.add_ext(ext.as_ref());
+        }
+        self
+    }
+
+    /// replace the allowed exts and sufs with these sufs, dropping the ones added before
     ///
-    /// e.g. sufs(vec![".rs"]).ext("toml") will allow files with .rs or .toml extension to be listed
+    /// any iterable of strings works, as with [`ListOption::set_exts`]
+    pub fn set_sufs<I, S>(&mut self, sufs: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.clear_sufs();
+        for suf in sufs {
+            self.add_suf(suf.as_ref());
+        }
+        self
+    }
+
+    /// drop all the allowed exts and sufs, so files with any suffix will be listed
+    pub fn clear_sufs(&mut self) -> &mut Self {
+        self.exts.clear();
+        self.sufs.clear();
+        self
+    }
+
+    /// append one ext to the excluded exts
     ///
-    /// e.g. sufs(vec!["rs"]).exts(vec!["toml"]) will only allow files with .toml extensions to be listed
+    /// entries with one of the excluded exts or sufs are never listed,
+    /// even if they have one of the allowed exts or sufs,
+    /// e.g. ext("rs").exclude_suf("_test.rs") lists main.rs but not main_test.rs
     ///
-    pub fn sufs(&mut self, sufs: Vec<&str>) -> &mut Self {
-        self.sufs = sufs.iter().map(|s| s.to_string()).collect();
+    /// exts and sufs are matched as with [`ListOption::add_ext`] and [`ListOption::add_suf`]
+    pub fn exclude_ext(&mut self, ext: &str) -> &mut Self {
+        push_unique(&mut self.exclude_exts, normalize_ext(ext));
+        self
+    }
+
+    /// append one suf to the excluded suffixes, see [`ListOption::exclude_ext`]
+    pub fn exclude_suf(&mut self, suf: &str) -> &mut Self {
+        push_unique(&mut self.exclude_sufs, suf);
+        self
+    }
+
+    /// replace the excluded exts with these
+    pub fn exclude_exts<I, S>(&mut self, exts: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.exclude_exts.clear();
+        for ext in exts {
+            self.exclude_ext(ext.as_ref());
+        }
+        self
+    }
+
+    /// replace the excluded sufs with these
+    pub fn exclude_sufs<I, S>(&mut self, sufs: I) -> &mut Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        self.exclude_sufs.clear();
+        for suf in sufs {
+            self.exclude_suf(suf.as_ref());
+        }
+        self
+    }
+
+    /// set what to do when some entry can't be read while listing
+    ///
+    /// - `ErrorPolicy::Abort`, the default, stops listing at the first error
+    /// - `ErrorPolicy::Skip` ignores the error and goes on with the siblings of the failed entry
+    /// - `ErrorPolicy::Collect` goes on like `Skip`, but keeps the errors, see [`ListOption::list_with_errors`]
+    pub fn on_error(&mut self, policy: ErrorPolicy) -> &mut Self {
+        self.error_policy = policy;
         self
     }
 }
@@ -141,11 +838,13 @@ impl ListOption {
     pub fn only_dir(&mut self) -> &mut Self {
         self.file = false;
         self.dir = true;
+        self.only_symlinks = false;
         self
     }
     pub fn only_file(&mut self) -> &mut Self {
         self.file = true;
         self.dir = false;
+        self.only_symlinks = false;
         self
     }
     pub fn only_hidden(&mut self) -> &mut Self {
@@ -158,6 +857,154 @@ impl ListOption {
         self.unhidden = true;
         self
     }
+    /// set if only the symlinks themselves are listed, whether they point to files
+    /// or directories, default false,
+    /// e.g. only_symlinks(true).file(false) lists the symlinks to directories
+    ///
+    /// only_file and only_dir set it back to false, as they list no symlink only
+    pub fn only_symlinks(&mut self, if_only: bool) -> &mut Self {
+        if if_only {
+            self.file = true;
+            self.dir = true;
+            self.symlink = true;
+        }
+        self.only_symlinks = if_only;
+        self
+    }
+}
+
+// drop the optional leading `.` of an ext
+pub(crate) fn normalize_ext(ext: &str) -> &str {
+    ext.strip_prefix('.').unwrap_or(ext)
+}
+
+// what the filters know about a path being listed
+#[derive(Clone, Copy)]
+pub(crate) struct Candidate<'a> {
+    pub(crate) path: &'a Path,
+    pub(crate) metadata: &'a Metadata,
+    // the path relative to the listing root, None for the root itself
+    pub(crate) rel_path: Option<&'a Path>,
+    // the canonical form of the listing root, if resolved, see ListOption::exclude_path
+    pub(crate) abs_root: Option<&'a Path>,
+    // if true, the entry is excluded by an ancestor, the ignore files or the prune rules,
+    // which are checked once by whoever makes the candidate, as they may call the predicates
+    pub(crate) excluded: bool,
+    // if true, the entry itself is a symlink, while the metadata is the one of its target if followed
+    pub(crate) is_symlink: bool,
+    // what the time filters compare against
+    pub(crate) start: ListStart,
+    // the name filters of the listing, folded once
+    pub(crate) names: &'a NameFilters,
+}
+
+/// read the options set in the ListOption
+impl ListOption {
+    /// check if the options set contradict each other, so that nothing could ever be listed
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !self.file && !self.dir {
+            return Err(ConfigError::NoKind);
+        }
+        if !self.hidden && !self.unhidden {
+            return Err(ConfigError::NoVisibility);
+        }
+        if self.depth_limit() == Some(0) {
+            return Err(ConfigError::ZeroLevel);
+        }
+        if self
+            .depth_limit()
+            .is_some_and(|level| self.min_depth > level)
+        {
+            return Err(ConfigError::MinDepthBeyondLevel);
+        }
+        if (!self.exts.is_empty() || !self.sufs.is_empty()) && !self.file && !self.sufs_on_dirs {
+            return Err(ConfigError::SufsWithoutFile);
+        }
+        if !self.exts.is_empty() && self.no_extension {
+            return Err(ConfigError::ExtsWithNoExtension);
+        }
+        if self.only_empty && self.skip_empty {
+            return Err(ConfigError::EmptyAndNonEmpty);
+        }
+        if self.only_readonly && self.skip_readonly {
+            return Err(ConfigError::ReadonlyAndNotReadonly);
+        }
+        if self.only_text && self.only_binary {
+            return Err(ConfigError::TextAndBinary);
+        }
+        Ok(())
+    }
+
+    /// if this option shows directories
+    pub fn is_dir_enabled(&self) -> bool {
+        self.dir
+    }
+
+    /// if this option shows files
+    pub fn is_file_enabled(&self) -> bool {
+        self.file
+    }
+
+    /// if this option shows symlinks
+    pub fn is_symlink_enabled(&self) -> bool {
+        self.symlink
+    }
+
+    /// if this option shows nothing but symlinks, see [`ListOption::only_symlinks`]
+    pub fn is_only_symlinks(&self) -> bool {
+        self.only_symlinks
+    }
+
+    /// if this option shows hidden files
+    pub fn is_hidden_enabled(&self) -> bool {
+        self.hidden
+    }
+
+    /// if this option shows unhidden files
+    pub fn is_unhidden_enabled(&self) -> bool {
+        self.unhidden
+    }
+
+    /// if this option lists recursively
+    pub fn is_recursive(&self) -> bool {
+        self.recursive
+    }
+
+    /// the level of recursion set by [`ListOption::level`],
+    /// `usize::MAX` for [`ListOption::unlimited_depth`]
+    pub fn max_level(&self) -> usize {
+        self.depth_limit().unwrap_or(usize::MAX)
+    }
+
+    /// the allowed exts, without their leading `.`
+    pub fn extensions(&self) -> &[String] {
+        &self.exts
+    }
+
+    /// the allowed sufs
+    pub fn suffixes(&self) -> &[String] {
+        &self.sufs
+    }
+
+    /// the excluded exts, without their leading `.`
+    pub fn excluded_extensions(&self) -> &[String] {
+        &self.exclude_exts
+    }
+
+    /// the excluded sufs
+    pub fn excluded_suffixes(&self) -> &[String] {
+        &self.exclude_sufs
+    }
+
+    /// if this option lists the root path itself
+    pub fn is_root_included(&self) -> bool {
+        self.include_root
+    }
+
+    /// what this option does when some entry can't be read
+    pub fn error_policy(&self) -> ErrorPolicy {
+        self.error_policy
+    }
 }
 
 /// impl list functionality
@@ -167,93 +1014,1462 @@ impl ListOption {
     /// if the path is a file, it will be listed if it matches the options set in the ListOption
     ///
     /// if the path is a directory, all files and directories in it will be listed if they match the options set in the ListOption
-    pub fn list<S>(&self, path: &S) -> Vec<String>
+    ///
+    /// if the path doesn't exist, nothing is listed, see [`ListOption::try_list`] to tell it apart
+    ///
+    /// this is a thin wrapper around [`ListOption::list_paths`], see it if you want the paths untouched
+    pub fn list<P>(&self, path: P) -> Vec<String>
     where
-        S: AsRef<OsStr> + ?Sized,
+        P: AsRef<Path>,
     {
-        let mut ret: Vec<String> = Vec::new();
-        if self.level == 0 {
-            return ret;
-        }
-        let path = Path::new(path);
-        if self.would_show(path) {
-            ret.push(path.to_str().unwrap().to_string());
-        }
-        if path.is_file() {
-            return ret;
-        }
-        if path.is_dir() {
-            // if is a directory, list all files and directories in it
-            for entry in path.read_dir().unwrap() {
-                let entry = entry.unwrap();
-                let path = entry.path();
-                let mut sub_option = self.clone();
-                if !self.recursive {
-                    sub_option.level = if self.level == 0 { 0 } else { self.level - 1 };
-                }
-                if self.would_show(&path) {
-                    ret.push(path.to_str().unwrap().to_string());
-                }
-                ret.extend(sub_option.inner_list(&path));
+        self.list_paths(path)
+            .into_iter()
+            .map(|path| path.to_string_lossy().into_owned())
+            .collect()
+    }
+
+    /// same as [`ListOption::list`], but returns the listed paths as `PathBuf`
+    ///
+    /// the paths are kept as they are read from the file system, without any lossy conversion,
+    /// so they can be fed straight into `std::fs` calls
+    ///
+    /// they are the root joined with the names under it, never canonicalized,
+    /// so on windows they keep the form of the root, without a `\\?\` prefix added,
+    /// while the std calls switch to that form by themselves for paths beyond MAX_PATH
+    pub fn list_paths<P>(&self, path: P) -> Vec<PathBuf>
+    where
+        P: AsRef<Path>,
+    {
+        self.iter(path).collect()
+    }
+
+    /// same as [`ListOption::list_paths`], but fills the given buffer instead of allocating a new one
+    ///
+    /// the buffer is cleared first, so nothing from a previous call is kept,
+    /// while its capacity is reused
+    pub fn list_into<P>(&self, path: P, out: &mut Vec<PathBuf>)
+    where
+        P: AsRef<Path>,
+    {
+        out.clear();
+        out.extend(self.iter(path));
+    }
+
+    /// lazily lists the paths at the given path, see [`ListIter`]
+    pub fn iter<P>(&self, path: P) -> ListIter<'_>
+    where
+        P: AsRef<Path>,
+    {
+        ListIter::new(self, path)
+    }
+
+    /// same as [`ListOption::list_paths`], but returns an error instead of panicking
+    /// when some directory in the tree can't be read
+    ///
+    /// unlike list, which lists nothing then, a root which doesn't exist, or a broken symlink
+    /// as the root, fails with `ListError::NotFound`, so a typo in the path isn't mistaken
+    /// for a directory without any match
+    ///
+    /// it fails up front if the options contradict each other, see [`ListOption::validate`],
+    /// then with `ErrorPolicy::Skip` it never fails,
+    /// with `ErrorPolicy::Collect` the first error is returned after the whole tree is listed
+    ///
+    /// a listing stopped by [`ListOption::timeout`] or [`ListOption::cancel_token`] is not
+    /// an error, it returns `Ok` with the paths found so far, use [`ListOption::try_list_report`]
+    /// to tell if it was cut short
+    pub fn try_list<P>(&self, path: P) -> Result<Vec<PathBuf>, ListError>
+    where
+        P: AsRef<Path>,
+    {
+        self.validate()?;
+        let (paths, errors) = self.list_with_errors(path);
+        match errors.into_iter().next() {
+            Some(err) => Err(err),
+            None => Ok(paths),
+        }
+    }
+
+    /// lists the entries at the given path, like [`ListOption::list_paths`],
+    /// but keeps what the traversal learned about each of them, see [`Entry`]
+    pub fn entries<P>(&self, path: P) -> Vec<Entry>
+    where
+        P: AsRef<Path>,
+    {
+        Walker::infallible(self, path).map(expect_listed).collect()
+    }
+
+    /// calls `visit` with each path that [`ListOption::list`] would list, in the same order,
+    /// without collecting them
+    ///
+    /// the traversal stops as soon as `visit` returns `ControlFlow::Break`,
+    /// no directory is read after that
+    pub fn walk<P, F>(&self, path: P, mut visit: F)
+    where
+        P: AsRef<Path>,
+        F: FnMut(&Path) -> ControlFlow<()>,
+    {
+        for entry in Walker::infallible(self, path) {
+            if visit(expect_listed(entry).path()).is_break() {
+                return;
             }
         }
-        ret
     }
-    fn inner_list(&self, path: &Path) -> Vec<String> {
-        let mut ret: Vec<String> = Vec::new();
-        if self.level == 0 {
-            return ret;
-        }
-        if path.is_dir() {
-            // if is a directory, list all files and directories in it
-            for entry in path.read_dir().unwrap() {
-                let entry = entry.unwrap();
-                let path = entry.path();
-                let mut sub_option = self.clone();
-                if !self.recursive {
-                    sub_option.level = if self.level == 0 { 0 } else { self.level - 1 };
-                }
-                if self.would_show(&path) {
-                    ret.push(path.to_str().unwrap().to_string());
+
+    /// returns the first path that [`ListOption::list`] would list,
+    /// without reading any directory after it is found
+    pub fn find_first<P>(&self, path: P) -> Option<PathBuf>
+    where
+        P: AsRef<Path>,
+    {
+        self.iter(path).next()
+    }
+
+    /// counts the paths that [`ListOption::list`] would list, without collecting them
+    pub fn count<P>(&self, path: P) -> usize
+    where
+        P: AsRef<Path>,
+    {
+        Walker::infallible(self, path).map(expect_listed).count()
+    }
+
+    /// check if [`ListOption::list`] would list any path, stopping at the first one found
+    pub fn has_match<P>(&self, path: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        self.find_first(path).is_some()
+    }
+
+    /// lists the paths at each of the given roots with the same options,
+    /// grouped by root in the given order
+    ///
+    /// a path resolving to the same file as a path listed before, e.g. when the roots overlap,
+    /// is only listed once
+    pub fn list_many<I>(&self, roots: I) -> Vec<PathBuf>
+    where
+        I: IntoIterator,
+        I::Item: AsRef<Path>,
+    {
+        let mut seen = HashSet::new();
+        let mut ret = Vec::new();
+        for root in roots {
+            for path in self.iter(root) {
+                let resolved = path.canonicalize().unwrap_or_else(|_| path.clone());
+                if seen.insert(resolved) {
+                    ret.push(path);
                 }
-                ret.extend(sub_option.inner_list(&path));
             }
         }
         ret
     }
 
+    /// lists the paths at the given path, returning them along with the errors met,
+    /// according to the [`ErrorPolicy`] set by [`ListOption::on_error`]
+    ///
+    /// - with `ErrorPolicy::Abort`, the paths listed before the first error and that error
+    /// - with `ErrorPolicy::Skip`, all the paths that can be read and no error
+    /// - with `ErrorPolicy::Collect`, all the paths that can be read and all the errors
+    pub fn list_with_errors<P>(&self, path: P) -> (Vec<PathBuf>, Vec<ListError>)
+    where
+        P: AsRef<Path>,
+    {
+        let mut paths = Vec::new();
+        let mut errors = Vec::new();
+        for res in self.try_iter(path) {
+            match res {
+                Ok(path) => paths.push(path),
+                Err(err) => errors.push(err),
+            }
+        }
+        (paths, errors)
+    }
+
+    /// lazily lists the paths at the given path, see [`TryListIter`]
+    pub fn try_iter<P>(&self, path: P) -> TryListIter<'_>
+    where
+        P: AsRef<Path>,
+    {
+        TryListIter::new(self, path)
+    }
+
     /// check if the path would be shown according to the options set in the ListOption
-    pub fn would_show<S>(&self, path: &S) -> bool
+    ///
+    /// a path whose metadata can't be fetched is never shown, and a symlink is checked
+    /// by its own name, see [`ListOption::follow_symlinks`]
+    pub fn would_show<P>(&self, path: P) -> bool
     where
-        S: AsRef<OsStr> + ?Sized,
+        P: AsRef<Path>,
     {
-        let check_hidden = |path: &Path| {
-            let base_name = path.file_name().unwrap().to_str().unwrap();
-            if self.hidden && base_name.starts_with('.') {
-                true
+        let path = path.as_ref();
+        let is_symlink = fs::symlink_metadata(path).is_ok_and(|m| m.is_symlink());
+        let metadata = if is_symlink && !self.follow_symlinks {
+            fs::symlink_metadata(path)
+        } else {
+            fs::metadata(path).or_else(|err| {
+                if self.broken_symlinks && is_dangling(&err) {
+                    fs::symlink_metadata(path)
+                } else {
+                    Err(err)
+                }
+            })
+        };
+        match metadata {
+            Ok(metadata) => {
+                let names = self.name_filters();
+                let mut candidate = Candidate {
+                    path,
+                    metadata: &metadata,
+                    rel_path: Some(path),
+                    abs_root: None,
+                    excluded: false,
+                    is_symlink,
+                    start: self.start(),
+                    names: &names,
+                };
+                candidate.excluded = self.prunes(&candidate);
+                self.show_with(&candidate).unwrap_or(false)
+            }
+            Err(_) => false,
+        }
+    }
+
+    // the levels listed under the root, None for the whole tree
+    pub(crate) fn depth_limit(&self) -> Option<usize> {
+        match self.level {
+            None if self.recursive => None,
+            level => Some(level.unwrap_or(1)),
+        }
+    }
+
+    // check if the path would be shown, with its metadata already fetched
+    pub(crate) fn show_with(&self, candidate: &Candidate) -> Result<bool, ListError> {
+        let Candidate { path, metadata, .. } = *candidate;
+        // only a symlink not followed, or broken, comes with the metadata of its own, as a Symlink
+        let Some(kind) = EntryKind::of(metadata) else {
+            return Ok(false);
+        };
+        let kind = if self.is_bundle(path, metadata) {
+            EntryKind::File
+        } else {
+            kind
+        };
+        let check_level = || self.depth_limit() != Some(0);
+        // the name is the one listed, not the one of a symlink target, only the kind follows
+        // the link
+        let names = candidate.names;
+        let name = names.fold_name(listed_name(path));
+        let excluded = candidate.excluded || self.excludes_name(names, &name, kind);
+        let reincluded = excluded && self.is_reincluded(candidate);
+        // skip the include filters, which may read the file, when it is excluded for good
+        if !resolve(true, excluded, reincluded) {
+            return Ok(false);
+        }
+        #[cfg(feature = "glob")]
+        let check_glob = || {
+            candidate
+                .rel_path
+                .is_none_or(|rel| self.globs.is_match(rel, self.ignore_case))
+        };
+        #[cfg(not(feature = "glob"))]
+        let check_glob = || true;
+        #[cfg(feature = "regex")]
+        let check_path_regex = || {
+            self.path_regex
+                .as_ref()
+                .is_none_or(|regex| regex.is_path_match(candidate.path))
+        };
+        #[cfg(not(feature = "regex"))]
+        let check_path_regex = || true;
+        #[cfg(all(unix, feature = "xattr"))]
+        let check_xattrs = || self.show_xattrs(candidate);
+        #[cfg(not(all(unix, feature = "xattr")))]
+        let check_xattrs = || Ok::<_, ListError>(true);
+        let check_filters = || {
+            self.filters
+                .iter()
+                .all(|filter| filter.test(candidate.path))
+        };
+        let check_symlink = || {
+            if candidate.is_symlink {
+                self.symlink
+            } else {
+                !self.only_symlinks
+            }
+        };
+        // the root is given explicitly, so it is shown by include_root whether hidden or not
+        let hidden_attr = candidate
+            .rel_path
+            .map(|_| self.is_hidden_by_attribute(metadata));
+        let included = check_level()
+            && check_symlink()
+            && self.show_name(names, &name, kind, hidden_attr)
+            && check_glob()
+            && check_path_regex()
+            && self.show_path_len(candidate.path)
+            && self.show_windows_names(candidate.path, candidate.rel_path)
+            && self.show_metadata(candidate)?
+            && check_xattrs()?
+            && check_filters()
+            && self.show_content(candidate)?;
+        Ok(resolve(included, excluded, reincluded))
+    }
+
+    /// check if an entry with the given file name and kind would be shown,
+    /// only by the kind and the name based rules, without touching the file system
+    ///
+    /// e.g. would_show_name("main.rs", EntryKind::File) is true with the default options,
+    /// and the name of a symlink is checked like the one of a file
+    pub fn would_show_name(&self, name: &str, kind: EntryKind) -> bool {
+        let names = self.name_filters();
+        let name = names.fold_name(OsStr::new(name));
+        let check_symlink = kind == EntryKind::Symlink || !self.only_symlinks;
+        resolve(
+            check_symlink && self.show_name(&names, &name, kind, Some(false)),
+            self.excludes_name(&names, &name, kind),
+            false,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_show_only_under_their_flags() {
+        let dir = TestDir::new();
+        dir.file("file.txt", b"");
+        dir.file("sub/inner.txt", b"");
+        dir.symlink("file.txt", "link_file");
+        dir.symlink("sub", "link_dir");
+        dir.symlink("missing", "dangling");
+        let list =
+            |option: &mut ListOption| dir.rel(option.include_root(false).list_paths(dir.path()));
+        assert_eq!(
+            list(&mut ListOption::default()),
+            ["file.txt", "link_dir", "link_file", "sub"]
+        );
+        assert_eq!(
+            list(ListOption::default().only_symlinks(true)),
+            ["link_dir", "link_file"]
+        );
+        assert_eq!(
+            list(
+                ListOption::default()
+                    .only_symlinks(true)
+                    .include_broken_symlinks(true)
+            ),
+            ["dangling", "link_dir", "link_file"]
+        );
+        assert_eq!(
+            list(ListOption::default().only_symlinks(true).file(false)),
+            ["link_dir"]
+        );
+        assert_eq!(
+            list(ListOption::default().symlink(false)),
+            ["file.txt", "sub"]
+        );
+    }
+
+    #[test]
+    fn only_file_and_only_dir_clear_only_symlinks() {
+        let dir = TestDir::new();
+        dir.file("file.txt", b"");
+        dir.file("sub/inner.txt", b"");
+        let mut option = ListOption::default();
+        option.include_root(false).only_symlinks(true);
+        assert!(option.list_paths(dir.path()).is_empty());
+        option.only_file();
+        assert!(!option.is_only_symlinks());
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["file.txt"]);
+        option.only_symlinks(true).only_dir();
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["sub"]);
+        option.only_symlinks(true).only_symlinks(false);
+        assert_eq!(option, *ListOption::default().include_root(false));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn unreadable_dir_is_an_error_not_a_panic() {
+        if crate::test_dir::is_root() {
+            return;
+        }
+        let dir = TestDir::new();
+        dir.file("file.txt", b"");
+        let locked = dir.lock("locked");
+        let mut option = ListOption::default();
+        option.recursive(true);
+        match option.try_list(dir.path()) {
+            Err(ListError::ReadDir { path, .. }) => assert_eq!(path, locked),
+            res => panic!("expected a ReadDir error, got {:?}", res),
+        }
+        // the infallible apis list what can be read, the locked dir itself included
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["", "file.txt", "locked"]
+        );
+        assert!(option.try_list(&locked).is_err());
+        assert_eq!(option.list_paths(&locked), [locked]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn searchable_but_unreadable_dir_is_skipped() {
+        if crate::test_dir::is_root() {
+            return;
+        }
+        let dir = TestDir::new();
+        dir.file("a.txt", b"");
+        dir.file("locked/inner.txt", b"");
+        dir.file("z/b.txt", b"");
+        // with mode 311 the entries in it can be stat'ed, but not listed
+        let locked = dir.lock_with_mode("locked", 0o311);
+        assert!(locked.join("inner.txt").exists());
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        assert!(matches!(
+            option.try_list(dir.path()),
+            Err(ListError::ReadDir { path, .. }) if path == locked
+        ));
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a.txt", "locked", "z", "z/b.txt"]
+        );
+        option.include_root(true);
+        assert!(option.try_list(&locked).is_err());
+        assert_eq!(option.list_paths(&locked), [locked]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn error_policies_on_an_unreadable_subdir() {
+        if crate::test_dir::is_root() {
+            return;
+        }
+        let dir = TestDir::new();
+        dir.file("a.txt", b"");
+        dir.file("ok/b.txt", b"");
+        let locked = dir.lock("locked");
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true).include_root(false);
+        let all = ["a.txt", "locked", "ok", "ok/b.txt"];
+
+        let (paths, errors) = option
+            .on_error(ErrorPolicy::Abort)
+            .list_with_errors(dir.path());
+        assert_eq!(dir.rel(paths), ["a.txt", "locked"]);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path(), Some(locked.as_path()));
+
+        let (paths, errors) = option
+            .on_error(ErrorPolicy::Skip)
+            .list_with_errors(dir.path());
+        assert_eq!(dir.rel(paths), all);
+        assert!(errors.is_empty());
+        assert_eq!(dir.rel(option.try_list(dir.path()).unwrap()), all);
+
+        let (paths, errors) = option
+            .on_error(ErrorPolicy::Collect)
+            .list_with_errors(dir.path());
+        assert_eq!(dir.rel(paths), all);
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].path(), Some(locked.as_path()));
+        assert!(matches!(
+            option.try_list(dir.path()),
+            Err(ListError::ReadDir { .. })
+        ));
+    }
+
+    #[test]
+    fn find_first_and_has_match_stop_at_the_first_match() {
+        use std::sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        };
+        let dir = TestDir::new();
+        for i in 0..20 {
+            dir.file(&format!("sub/{}.txt", i), b"");
+        }
+        let checked = Arc::new(AtomicUsize::new(0));
+        let counter = checked.clone();
+        let mut option = ListOption::default();
+        option.recursive(true).filter_fn(move |path| {
+            counter.fetch_add(1, Ordering::Relaxed);
+            path.is_file()
+        });
+        let first = option.find_first(dir.path()).unwrap();
+        assert!(first.starts_with(dir.join("sub")));
+        // the root, sub and the first file
+        assert_eq!(checked.swap(0, Ordering::Relaxed), 3);
+        assert!(option.has_match(dir.path()));
+        assert_eq!(checked.swap(0, Ordering::Relaxed), 3);
+        assert_eq!(option.count(dir.path()), 20);
+        assert_eq!(checked.load(Ordering::Relaxed), 22);
+    }
+
+    #[test]
+    fn count_and_has_match_agree_with_list() {
+        let dir = TestDir::new();
+        for path in ["a.txt", "b.rs", ".c.txt", "d/e.txt", "d/f/g.rs", ".h/i.txt"] {
+            dir.file(path, b"");
+        }
+        let configs: [fn(&mut ListOption); 8] = [
+            |_| {},
+            |o| {
+                o.recursive(true);
+            },
+            |o| {
+                o.recursive(true).hidden(true);
+            },
+            |o| {
+                o.recursive(true).only_file().ext("txt");
+            },
+            |o| {
+                o.level(2).only_dir();
+            },
+            |o| {
+                o.recursive(true).include_root(false).max_results(3);
+            },
+            |o| {
+                o.recursive(true).ext("md");
+            },
+            |o| {
+                o.recursive(true).include_root(false).ext("md");
+            },
+        ];
+        for configure in configs {
+            let mut option = ListOption::default();
+            configure(&mut option);
+            let listed = option.list(dir.path());
+            assert_eq!(option.count(dir.path()), listed.len(), "{:?}", option);
+            assert_eq!(option.has_match(dir.path()), !listed.is_empty());
+        }
+    }
+
+    #[test]
+    fn recursive_keeps_the_level_as_max_depth() {
+        let dir = TestDir::new();
+        dir.file("1/2/3/4/5.txt", b"");
+        dir.file("1/top.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).level(2);
+        let two = ["", "1", "1/2", "1/top.txt"];
+        assert_eq!(dir.rel(option.list_paths(dir.path())), two);
+        option.breadth_first(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), two);
+        option.breadth_first(false).contents_first(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), two);
+        option.contents_first(false).min_depth(2);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["1/2", "1/top.txt"]);
+        option.min_depth(0).recursive(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), two);
+        option.unlimited_depth();
+        assert_eq!(option.count(dir.path()), 7);
+
+        // no directory at the last level is read
+        #[cfg(unix)]
+        if !crate::test_dir::is_root() {
+            dir.lock("1/locked");
+            option.recursive(true).level(2);
+            assert_eq!(option.try_list(dir.path()).unwrap().len(), 5);
+        }
+    }
+
+    #[test]
+    fn add_set_and_clear_sufs() {
+        let mut option = ListOption::default();
+        option
+            .add_ext("rs")
+            .add_ext(".rs")
+            .add_suf("_test.rs")
+            .ext("toml");
+        assert_eq!(option.extensions(), ["rs", "toml"]);
+        assert_eq!(option.suffixes(), ["_test.rs"]);
+        option.set_exts(["md"]);
+        assert_eq!(option.extensions(), ["md"]);
+        assert!(option.suffixes().is_empty());
+        option.add_suf(".txt").set_sufs([".log"]);
+        assert!(option.extensions().is_empty());
+        assert_eq!(option.suffixes(), [".log"]);
+        option.ext("rs").clear_sufs();
+        assert!(option.extensions().is_empty() && option.suffixes().is_empty());
+        assert!(option.would_show_name("any.thing", EntryKind::File));
+    }
+
+    #[test]
+    fn sufs_take_any_iterable_of_strings() {
+        let mut option = ListOption::default();
+        option.exts(["rs", "toml"]);
+        assert_eq!(option.extensions(), ["rs", "toml"]);
+        option.exts(vec![String::from("md")]);
+        assert_eq!(option.extensions(), ["md"]);
+        let owned = [String::from("txt"), String::from("log")];
+        option.exts(owned.iter().filter(|ext| ext.starts_with('t')));
+        assert_eq!(option.extensions(), ["txt"]);
+        let sufs = vec![".a", ".b"];
+        option.sufs(&sufs);
+        assert_eq!(option.suffixes(), [".a", ".b"]);
+        option.exts(Vec::<&str>::new());
+        assert!(option.extensions().is_empty() && option.suffixes().is_empty());
+        option.exclude_exts(["o"]).exclude_sufs(Some("~"));
+        assert_eq!(option.excluded_extensions(), ["o"]);
+        assert_eq!(option.excluded_suffixes(), ["~"]);
+    }
+
+    #[test]
+    fn would_show_name_by_kind_and_name_only() {
+        let mut option = ListOption::default();
+        option.ext("rs");
+        // none of these exist
+        assert!(option.would_show_name("main.rs", EntryKind::File));
+        assert!(!option.would_show_name("main.txt", EntryKind::File));
+        assert!(option.would_show_name("src", EntryKind::Dir));
+        assert!(option.would_show_name("link.rs", EntryKind::Symlink));
+        assert!(!option.would_show_name(".hidden.rs", EntryKind::File));
+        option.hidden(true).dir(false);
+        assert!(option.would_show_name(".hidden.rs", EntryKind::File));
+        assert!(!option.would_show_name("src", EntryKind::Dir));
+        option.exclude_suf("_test.rs");
+        assert!(!option.would_show_name("main_test.rs", EntryKind::File));
+        option.only_symlinks(true);
+        assert!(!option.would_show_name("main.rs", EntryKind::File));
+        assert!(option.would_show_name("main.rs", EntryKind::Symlink));
+    }
+
+    #[test]
+    fn excluded_sufs_win_over_allowed_ones() {
+        let dir = TestDir::new();
+        for path in [
+            "main.rs",
+            "main_test.rs",
+            "lib.rs.bak",
+            "notes.txt",
+            "build.o/x.rs",
+        ] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .ext("rs")
+            .exclude_suf("_test.rs")
+            .exclude_ext("o");
+        // the excludes don't apply to directories by default
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["build.o", "build.o/x.rs", "main.rs"]
+        );
+        // then the directory itself is excluded, but not pruned, see exclude_dir for that
+        option.apply_sufs_to_dirs(true).clear_sufs();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["build.o/x.rs", "lib.rs.bak", "main.rs", "notes.txt"]
+        );
+    }
+
+    #[test]
+    fn hidden_dirs_are_pruned_unless_descended() {
+        let dir = TestDir::new();
+        for path in [".git/objects/ab", ".config/app/settings.toml", "src/a.rs"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["src", "src/a.rs"]);
+        option.descend_hidden(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            [
+                ".config/app",
+                ".config/app/settings.toml",
+                ".git/objects",
+                ".git/objects/ab",
+                "src",
+                "src/a.rs"
+            ]
+        );
+        option.descend_hidden(false).hidden(true);
+        assert_eq!(option.count(dir.path()), 8);
+    }
+
+    #[test]
+    fn hidden_roots_are_listed_and_read() {
+        let dir = TestDir::new();
+        dir.file(".config/app.toml", b"");
+        dir.file(".config/.secret", b"");
+        let root = dir.join(".config");
+        let mut option = ListOption::default();
+        option.recursive(true);
+        assert_eq!(
+            dir.rel(option.list_paths(&root)),
+            [".config", ".config/app.toml"]
+        );
+        // the unhidden filters don't drop the root either
+        option.only_hidden();
+        assert_eq!(
+            dir.rel(option.list_paths(&root)),
+            [".config", ".config/.secret"]
+        );
+        // nor for a hidden file
+        let file = dir.join(".config/.secret");
+        assert_eq!(ListOption::default().list_paths(&file), [file]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn special_files_under_their_flags() {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt, os::unix::net::UnixListener};
+
+        let dir = TestDir::new();
+        dir.file("file.txt", b"");
+        let fifo = dir.join("pipe");
+        let c_fifo = CString::new(fifo.as_os_str().as_bytes()).unwrap();
+        // SAFETY: the path is a valid nul terminated string
+        assert_eq!(unsafe { libc::mkfifo(c_fifo.as_ptr(), 0o644) }, 0);
+        let _socket = UnixListener::bind(dir.join("sock")).unwrap();
+        let mut option = ListOption::default();
+        option.include_root(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["file.txt"]);
+        option.fifos(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["file.txt", "pipe"]);
+        option.fifos(false).sockets(true);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["file.txt", "sock"]);
+        // the content filters don't open them
+        option.special_files(true).only_text(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["file.txt", "pipe", "sock"]
+        );
+        let entries = option.entries(dir.path());
+        let kinds: Vec<_> = entries.iter().map(|entry| entry.kind()).collect();
+        assert!(kinds.contains(&EntryKind::Fifo) && kinds.contains(&EntryKind::Socket));
+        let null = Path::new("/dev/null");
+        if null.exists() {
+            assert!(!ListOption::default().would_show(null));
+            assert!(ListOption::default().devices(true).would_show(null));
+        }
+    }
+
+    #[test]
+    fn validate_reports_each_contradiction() {
+        let option = ListOption::default;
+        let cases = [
+            (option().file(false).dir(false).build(), ConfigError::NoKind),
+            (
+                option().hidden(false).unhidden(false).build(),
+                ConfigError::NoVisibility,
+            ),
+            (
+                option().recursive(true).level(0).build(),
+                ConfigError::ZeroLevel,
+            ),
+            (
+                option().level(2).min_depth(3).build(),
+                ConfigError::MinDepthBeyondLevel,
+            ),
+            (
+                option().only_dir().ext("rs").build(),
+                ConfigError::SufsWithoutFile,
+            ),
+            (
+                option().ext("rs").no_extension(true).build(),
+                ConfigError::ExtsWithNoExtension,
+            ),
+            (
+                option()
+                    .only_empty_files(true)
+                    .skip_empty_files(true)
+                    .build(),
+                ConfigError::EmptyAndNonEmpty,
+            ),
+            (
+                option().only_readonly(true).skip_readonly(true).build(),
+                ConfigError::ReadonlyAndNotReadonly,
+            ),
+            (
+                option().only_text(true).only_binary(true).build(),
+                ConfigError::TextAndBinary,
+            ),
+        ];
+        let dir = TestDir::new();
+        for (option, expected) in cases {
+            assert_eq!(option.validate(), Err(expected));
+            assert!(matches!(
+                option.try_list(dir.path()),
+                Err(ListError::Config(err)) if err == expected
+            ));
+        }
+        assert_eq!(ListOption::default().validate(), Ok(()));
+        // the same options are fine once they don't contradict each other
+        let mut option = ListOption::default();
+        option
+            .only_dir()
+            .ext("rs")
+            .apply_sufs_to_dirs(true)
+            .level(3)
+            .min_depth(3);
+        assert_eq!(option.validate(), Ok(()));
+    }
+
+    #[test]
+    fn list_and_list_paths_agree() {
+        let dir = TestDir::new();
+        dir.file("a.txt", b"");
+        dir.file("sub/b.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true);
+        let paths = option.list_paths(dir.path());
+        assert_eq!(paths[0], dir.path());
+        assert_eq!(
+            option.list(dir.path()),
+            paths
+                .iter()
+                .map(|path| path.to_string_lossy().into_owned())
+                .collect::<Vec<_>>()
+        );
+        // a path is accepted in any form
+        assert_eq!(option.list_paths(dir.path().to_str().unwrap()), paths);
+        assert_eq!(option.list_paths(dir.path().as_os_str()), paths);
+    }
+
+    #[test]
+    fn spaces_and_unicode_are_listed_untouched() {
+        let dir = TestDir::new();
+        dir.file("a b/é 日.txt", b"");
+        dir.file("ünï cødé.md", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true).include_root(false);
+        let paths = option.list_paths(dir.path());
+        assert_eq!(
+            paths,
+            [
+                dir.join("a b"),
+                dir.join("a b/é 日.txt"),
+                dir.join("ünï cødé.md")
+            ]
+        );
+        assert_eq!(
+            option.list(dir.path()),
+            paths
+                .iter()
+                .map(|path| path.to_str().unwrap())
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn iter_is_lazy_and_lists_like_list_paths() {
+        let dir = TestDir::new();
+        dir.file("a/1.txt", b"");
+        dir.file("b/2.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true);
+        assert_eq!(
+            option.iter(dir.path()).collect::<Vec<_>>(),
+            option.list_paths(dir.path())
+        );
+        let mut iter = option.iter(dir.path());
+        assert_eq!(iter.next().unwrap(), dir.path());
+        assert_eq!(iter.next().unwrap(), dir.join("a"));
+        // b is not read yet, so what is added under it is still listed
+        dir.file("b/3.txt", b"");
+        assert_eq!(
+            dir.rel(iter.collect()),
+            ["a/1.txt", "b", "b/2.txt", "b/3.txt"]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_names_are_listed_untouched() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+        let dir = TestDir::new();
+        let name = OsStr::from_bytes(b"bad\xff.txt");
+        if std::fs::write(dir.path().join(name), b"").is_err() {
+            // the file system only takes utf-8 names
+            return;
+        }
+        let mut option = ListOption::default();
+        option.include_root(false);
+        assert_eq!(option.list_paths(dir.path()), [dir.path().join(name)]);
+        assert_eq!(
+            option.list(dir.path()),
+            [dir.path().join("bad\u{fffd}.txt").to_string_lossy()]
+        );
+        assert_eq!(
+            ListOption::default()
+                .ext("txt")
+                .include_root(false)
+                .count(dir.path()),
+            1
+        );
+    }
+
+    #[test]
+    fn walk_stops_at_the_first_break() {
+        let dir = TestDir::new();
+        for path in ["a/1.txt", "b/2.txt", "c/3.txt"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true);
+        let mut walked = Vec::new();
+        option.walk(dir.path(), |path| {
+            walked.push(path.to_path_buf());
+            ControlFlow::Continue(())
+        });
+        assert_eq!(walked, option.list_paths(dir.path()));
+        let mut walked = Vec::new();
+        option.walk(dir.path(), |path| {
+            walked.push(path.to_path_buf());
+            if path.ends_with("1.txt") {
+                ControlFlow::Break(())
             } else {
-                self.unhidden && !base_name.starts_with('.')
+                ControlFlow::Continue(())
             }
+        });
+        assert_eq!(dir.rel(walked), ["", "a", "a/1.txt"]);
+    }
+
+    #[test]
+    fn list_many_groups_by_root_and_lists_overlaps_once() {
+        let dir = TestDir::new();
+        dir.file("a/1.txt", b"");
+        dir.file("b/2.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true);
+        let paths = option.list_many([dir.join("b"), dir.join("a"), dir.path().to_path_buf()]);
+        assert_eq!(dir.rel(paths.clone()), ["", "a", "a/1.txt", "b", "b/2.txt"]);
+        // b first, then a, then only the root itself is new
+        assert_eq!(paths[0], dir.join("b"));
+        assert_eq!(paths[2], dir.join("a"));
+        assert_eq!(paths[4], dir.path());
+        assert!(option.list_many(Vec::<PathBuf>::new()).is_empty());
+        // a file root then the directory holding it, and the other way round
+        let file = dir.join("a/1.txt");
+        assert_eq!(
+            option.list_many([file.clone(), dir.join("a")]),
+            [file.clone(), dir.join("a")]
+        );
+        assert_eq!(
+            option.list_many([dir.join("a"), file.clone()]),
+            [dir.join("a"), file]
+        );
+    }
+
+    #[test]
+    fn list_into_clears_the_buffer() {
+        let dir = TestDir::new();
+        dir.file("a.txt", b"");
+        dir.file("b.txt", b"");
+        dir.file("sub/c.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true);
+        let mut out = vec![PathBuf::from("stale")];
+        option.list_into(dir.path(), &mut out);
+        assert_eq!(
+            dir.rel(out.clone()),
+            ["", "a.txt", "b.txt", "sub", "sub/c.txt"]
+        );
+        // nothing of the first listing is left, neither a removed file nor the other root
+        std::fs::remove_file(dir.join("sub/c.txt")).unwrap();
+        dir.file("sub/d.txt", b"");
+        option.list_into(dir.join("sub"), &mut out);
+        assert_eq!(out, [dir.join("sub"), dir.join("sub/d.txt")]);
+        std::fs::remove_file(dir.join("b.txt")).unwrap();
+        option.list_into(dir.path(), &mut out);
+        assert_eq!(dir.rel(out), ["", "a.txt", "sub", "sub/d.txt"]);
+    }
+
+    #[test]
+    fn include_root_on_dirs_and_files() {
+        let dir = TestDir::new();
+        dir.file("a.txt", b"");
+        let mut option = ListOption::default();
+        assert!(option.is_root_included());
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["", "a.txt"]);
+        assert_eq!(option.list_paths(dir.join("a.txt")), [dir.join("a.txt")]);
+        option.include_root(false);
+        assert!(!option.is_root_included());
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a.txt"]);
+        assert!(option.list_paths(dir.join("a.txt")).is_empty());
+    }
+
+    #[test]
+    fn getters_and_equality_follow_the_setters() {
+        let mut option = ListOption::default();
+        assert_eq!(option, ListOption::new());
+        option
+            .dir(false)
+            .hidden(true)
+            .level(3)
+            .exts(["rs"])
+            .exclude_sufs(["~"])
+            .on_error(ErrorPolicy::Collect);
+        assert!(!option.is_dir_enabled() && option.is_file_enabled());
+        assert!(option.is_hidden_enabled() && option.is_unhidden_enabled());
+        assert!(!option.is_only_symlinks());
+        assert!(!option.is_recursive());
+        assert_eq!(option.max_level(), 3);
+        assert_eq!(option.unlimited_depth().max_level(), usize::MAX);
+        assert_eq!(option.extensions(), ["rs"]);
+        assert_eq!(option.excluded_suffixes(), ["~"]);
+        assert!(option.excluded_extensions().is_empty());
+        assert_eq!(option.error_policy(), ErrorPolicy::Collect);
+        assert_ne!(option, ListOption::default());
+        assert_eq!(option, option.clone());
+    }
+
+    #[test]
+    fn every_builder_with_a_getter_round_trips() {
+        let default = ListOption::default();
+        assert!(default.is_dir_enabled() && default.is_file_enabled());
+        assert!(default.is_symlink_enabled() && !default.is_only_symlinks());
+        assert!(!default.is_hidden_enabled() && default.is_unhidden_enabled());
+        assert!(!default.is_recursive() && default.max_level() == 1);
+        assert!(default.is_root_included());
+        assert_eq!(default.error_policy(), ErrorPolicy::Abort);
+
+        let mut option = ListOption::default();
+        option
+            .dir(false)
+            .file(false)
+            .symlink(false)
+            .hidden(true)
+            .unhidden(false)
+            .recursive(true)
+            .include_root(false)
+            .exts(["rs"])
+            .add_suf("_test.rs")
+            .exclude_exts(["o"])
+            .exclude_sufs(["~"])
+            .on_error(ErrorPolicy::Skip);
+        assert!(!option.is_dir_enabled());
+        assert!(!option.is_file_enabled());
+        assert!(!option.is_symlink_enabled());
+        assert!(option.is_hidden_enabled());
+        assert!(!option.is_unhidden_enabled());
+        assert!(option.is_recursive());
+        assert_eq!(option.max_level(), usize::MAX);
+        assert!(!option.is_root_included());
+        assert_eq!(option.extensions(), ["rs"]);
+        assert_eq!(option.suffixes(), ["_test.rs"]);
+        assert_eq!(option.excluded_extensions(), ["o"]);
+        assert_eq!(option.excluded_suffixes(), ["~"]);
+        assert_eq!(option.error_policy(), ErrorPolicy::Skip);
+        option.sufs([".md"]);
+        assert!(option.extensions().is_empty());
+        assert_eq!(option.suffixes(), [".md"]);
+        option.level(2);
+        assert_eq!(option.max_level(), 2);
+        // only_symlinks turns the kinds it needs back on
+        option.only_symlinks(true);
+        assert!(option.is_only_symlinks());
+        assert!(option.is_symlink_enabled() && option.is_file_enabled() && option.is_dir_enabled());
+        assert_ne!(option, default);
+    }
+
+    #[test]
+    fn build_ends_a_chain_with_an_owned_option() {
+        struct Config {
+            option: ListOption,
+        }
+        let config = Config {
+            option: ListOption::new().recursive(true).ext("rs").build(),
         };
-        let check_file_dir =
-            |path: &Path| (path.is_file() && self.file) || (path.is_dir() && self.dir);
-        let check_level = || self.recursive || self.level > 0;
-        let check_ext = |path: &Path| {
-            self.sufs.is_empty()
-                || self
-                    .sufs
-                    .iter()
-                    .any(|suf| path.to_str().unwrap().ends_with(suf))
+        let mut option = ListOption::new();
+        option.recursive(true).ext("rs");
+        assert_eq!(config.option, option);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_symlinks_by_their_own_name() {
+        let dir = TestDir::new();
+        dir.symlink("missing.txt", "old.log");
+        dir.symlink("missing.log", "old.txt");
+        dir.symlink("loop", "loop");
+        let mut option = ListOption::default();
+        option.include_root(false);
+        assert!(option.list_paths(dir.path()).is_empty());
+        option.include_broken_symlinks(true);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["loop", "old.log", "old.txt"]
+        );
+        let entries = option.entries(dir.path());
+        assert!(entries
+            .iter()
+            .all(|entry| entry.kind() == EntryKind::Symlink));
+        option.ext("log");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["old.log"]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn follow_symlinks_or_list_them_as_they_are() {
+        let dir = TestDir::new();
+        dir.file("sub/inner.txt", b"");
+        dir.symlink("sub", "link");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["link", "link/inner.txt", "sub", "sub/inner.txt"]
+        );
+        option.follow_symlinks(false);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["link", "sub", "sub/inner.txt"]
+        );
+        // a symlink is listed under its own flag then, whatever it points to
+        option.file(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["link", "sub"]);
+        option.symlink(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["sub"]);
+        // while the root is followed either way
+        let mut option = ListOption::default();
+        option.follow_symlinks(false).include_root(false);
+        assert_eq!(
+            option.list_paths(dir.join("link")),
+            [dir.join("link/inner.txt")]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_are_classified_by_their_own_name() {
+        let dir = TestDir::new();
+        dir.file("data/app-2024.log", b"");
+        dir.file("data/.cache.txt", b"");
+        dir.symlink("data/app-2024.log", "current.log.lnk");
+        dir.symlink("data/app-2024.log", ".secret");
+        dir.symlink("data/.cache.txt", "cache.txt");
+        let mut option = ListOption::default();
+        option.include_root(false).only_file();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["cache.txt", "current.log.lnk"]
+        );
+        option.ext("lnk");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["current.log.lnk"]);
+        option.set_exts(["log"]);
+        assert!(option.list_paths(dir.path()).is_empty());
+    }
+
+    #[test]
+    fn min_depth_and_level_bound_the_depths() {
+        let dir = TestDir::new();
+        dir.file("a/b/c/d.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).min_depth(2);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a/b", "a/b/c", "a/b/c/d.txt"]
+        );
+        option.level(3);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a/b", "a/b/c"]);
+        option.min_depth(4);
+        assert!(option.list_paths(dir.path()).is_empty());
+        // the root is depth 0
+        option.min_depth(0).level(1);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["", "a"]);
+    }
+
+    #[test]
+    fn level_zero_lists_nothing_and_unlimited_depth_everything() {
+        let dir = TestDir::new();
+        dir.file("a/b/c/d.txt", b"");
+        let mut option = ListOption::default();
+        option.level(0);
+        assert!(option.list_paths(dir.path()).is_empty());
+        assert!(matches!(
+            option.try_list(dir.path()),
+            Err(ListError::Config(ConfigError::ZeroLevel))
+        ));
+        option.unlimited_depth();
+        assert_eq!(option.list_paths(dir.path()).len(), 5);
+        assert!(option.try_list(dir.path()).is_ok());
+    }
+
+    #[test]
+    fn breadth_first_lists_level_by_level() {
+        let dir = TestDir::new();
+        dir.file("a/b/1.txt", b"");
+        dir.file("c/2.txt", b"");
+        dir.file("3.txt", b"");
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .breadth_first(true)
+            .include_root(false);
+        let depths: Vec<_> = option
+            .entries(dir.path())
+            .iter()
+            .map(|entry| entry.depth())
+            .collect();
+        assert_eq!(depths, [1, 1, 1, 2, 2, 3]);
+        // the shallowest match is found first
+        option.ext("txt").only_file();
+        assert_eq!(option.find_first(dir.path()), Some(dir.join("3.txt")));
+    }
+
+    #[test]
+    fn contents_first_puts_parents_after_their_children() {
+        let dir = TestDir::new();
+        dir.file("a/b/1.txt", b"");
+        dir.file("a/2.txt", b"");
+        dir.file("3.txt", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).contents_first(true);
+        let paths = option.list_paths(dir.path());
+        assert_eq!(paths.len(), 6);
+        assert_eq!(paths.last().unwrap(), dir.path());
+        for (i, path) in paths.iter().enumerate() {
+            assert!(!paths[..i]
+                .iter()
+                .any(|before| before != path && path.starts_with(before)));
+        }
+        // removing them in order never meets a directory which is not empty
+        for path in paths {
+            if path.is_dir() {
+                std::fs::remove_dir(path).unwrap();
+            } else {
+                std::fs::remove_file(path).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn sorted_by_name_byte_by_byte() {
+        let dir = TestDir::new();
+        for path in ["b/2.txt", "B.txt", "a.txt", "b/1.txt", "_c"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true).include_root(false);
+        let listed: Vec<_> = option
+            .list_paths(dir.path())
+            .into_iter()
+            .map(|path| path.strip_prefix(dir.path()).unwrap().to_path_buf())
+            .collect();
+        let expected = ["B.txt", "_c", "a.txt", "b", "b/1.txt", "b/2.txt"];
+        assert_eq!(listed, expected.map(PathBuf::from));
+        // and the same in breadth first
+        option.breadth_first(true);
+        assert_eq!(
+            option.list_paths(dir.path()),
+            expected.map(|path| dir.join(path))
+        );
+    }
+
+    #[test]
+    fn dirs_first_and_files_first_replace_each_other() {
+        let dir = TestDir::new();
+        for path in ["a.txt", "b/1.txt", "c.txt", "d/2.txt"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option.sorted(true).include_root(false);
+        let list = |option: &ListOption| -> Vec<_> {
+            option
+                .list_paths(dir.path())
+                .into_iter()
+                .map(|path| path.file_name().unwrap().to_string_lossy().into_owned())
+                .collect()
         };
-        let path = Path::new(path);
-        if !path.exists() {
-            return false;
-        }
-        let path = &path.canonicalize().unwrap();
-        path.exists()
-            && check_hidden(path)
-            && check_file_dir(path)
-            && check_level()
-            && check_ext(path)
+        option.dirs_first(true);
+        assert_eq!(list(&option), ["b", "d", "a.txt", "c.txt"]);
+        option.files_first(true);
+        assert_eq!(list(&option), ["a.txt", "c.txt", "b", "d"]);
+        // turning off the other one keeps this one
+        option.dirs_first(false);
+        assert_eq!(list(&option), ["a.txt", "c.txt", "b", "d"]);
+        option.files_first(false);
+        assert_eq!(list(&option), ["a.txt", "b", "c.txt", "d"]);
+    }
+
+    #[test]
+    fn max_results_caps_every_api() {
+        let dir = TestDir::new();
+        for path in ["a/1.txt", "a/2.txt", "b/3.txt", "4.txt"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option.recursive(true).sorted(true).max_results(3);
+        let paths = option.list_paths(dir.path());
+        assert_eq!(dir.rel(paths.clone()), ["", "4.txt", "a"]);
+        assert_eq!(option.iter(dir.path()).collect::<Vec<_>>(), paths);
+        assert_eq!(option.count(dir.path()), 3);
+        assert_eq!(option.entries(dir.path()).len(), 3);
+        assert_eq!(option.try_list(dir.path()).unwrap(), paths);
+        let mut walked = Vec::new();
+        option.walk(dir.path(), |path| {
+            walked.push(path.to_path_buf());
+            ControlFlow::Continue(())
+        });
+        assert_eq!(walked, paths);
+        option.max_results(0);
+        assert!(!option.has_match(dir.path()));
+        assert!(option.list_paths(dir.path()).is_empty());
+        assert_eq!(option.count(dir.path()), 0);
+        // a cap above the size of the tree cuts nothing
+        option.max_results(100);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["", "4.txt", "a", "a/1.txt", "a/2.txt", "b", "b/3.txt"]
+        );
+    }
+
+    #[test]
+    fn bundles_are_files_when_asked() {
+        let dir = TestDir::new();
+        dir.file("Foo.APP/Contents/Info.plist", b"");
+        dir.file("Bar.pkg/inner", b"");
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        assert_eq!(dir.rel(option.list_paths(dir.path())).len(), 5);
+        option.treat_bundles_as_files(true).only_file();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["Bar.pkg/inner", "Foo.APP"]
+        );
+        // only the filters take it as a file, the entry is still a directory
+        let kinds: Vec<_> = option
+            .sorted(true)
+            .entries(dir.path())
+            .iter()
+            .map(|entry| entry.kind())
+            .collect();
+        assert_eq!(kinds, [EntryKind::File, EntryKind::Dir]);
+        option.bundle_exts(["pkg"]);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["Bar.pkg", "Foo.APP/Contents/Info.plist"]
+        );
+        // a bundle as the root is still read
+        assert_eq!(
+            option.list_paths(dir.join("Bar.pkg")),
+            [dir.join("Bar.pkg/inner")]
+        );
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn filesystem_root_is_listed_without_panicking() {
+        let mut option = ListOption::default();
+        option.hidden(true).max_results(10);
+        let paths = option.list_paths("/");
+        assert_eq!(paths[0], Path::new("/"));
+        assert!(paths[1..]
+            .iter()
+            .all(|path| path.parent() == Some(Path::new("/"))));
+        // the root has no name to filter, so it is kept whatever the name filters
+        option.ext("txt").only_hidden();
+        assert_eq!(option.find_first("/"), Some(PathBuf::from("/")));
+    }
+
+    #[test]
+    fn missing_root_fails_try_list_only() {
+        let dir = TestDir::new();
+        let missing = dir.join("missing");
+        let option = ListOption::default();
+        assert!(option.list_paths(&missing).is_empty());
+        assert!(matches!(
+            option.try_list(&missing),
+            Err(ListError::NotFound { path }) if path == missing
+        ));
+        let (paths, errors) = option.list_with_errors(&missing);
+        assert!(paths.is_empty());
+        assert_eq!(errors.len(), 1);
+        // an empty directory is no error
+        assert_eq!(option.try_list(dir.path()).unwrap(), [dir.path()]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn broken_symlink_as_the_root_is_not_found() {
+        let dir = TestDir::new();
+        dir.symlink("missing", "link");
+        let link = dir.join("link");
+        assert!(matches!(
+            ListOption::default().try_list(&link),
+            Err(ListError::NotFound { path }) if path == link
+        ));
+    }
+
+    #[test]
+    fn entries_removed_while_listed_are_no_error() {
+        let dir = TestDir::new();
+        dir.file("a/1.txt", b"");
+        dir.file("b/2.txt", b"");
+        dir.file("c.txt", b"");
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .sorted(true)
+            .on_error(ErrorPolicy::Collect);
+        let mut iter = option.try_iter(dir.path());
+        assert_eq!(iter.next().unwrap().unwrap(), dir.path());
+        assert_eq!(iter.next().unwrap().unwrap(), dir.join("a"));
+        // the root is read whole, as it is sorted, while a is not read yet
+        std::fs::remove_dir_all(dir.join("a")).unwrap();
+        std::fs::remove_dir_all(dir.join("b")).unwrap();
+        std::fs::remove_file(dir.join("c.txt")).unwrap();
+        let rest: Result<Vec<_>, _> = iter.collect();
+        assert!(rest
+            .unwrap()
+            .iter()
+            .all(|path| !path.starts_with(dir.join("a"))));
+    }
+
+    #[test]
+    fn per_dir_cap_goes_on_with_the_rest_of_the_tree() {
+        let dir = TestDir::new();
+        for path in ["a/1", "a/2", "a/3", "b/4", "b/5"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .sorted(true)
+            .include_root(false)
+            .max_entries_per_dir(2);
+        let mut iter = option.try_iter(dir.path());
+        let listed: Result<Vec<_>, _> = iter.by_ref().collect();
+        assert_eq!(
+            dir.rel(listed.unwrap()),
+            ["a", "a/1", "a/2", "b", "b/4", "b/5"]
+        );
+        assert_eq!(iter.truncated_dirs(), [dir.join("a")]);
+        // the hidden entries count too, as they are read
+        dir.file("b/.h", b"");
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["a", "a/1", "a/2", "b", "b/4"]
+        );
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn lists_a_tree_beyond_max_path() {
+        let dir = TestDir::new();
+        let name = "a".repeat(50);
+        let rel = [name.as_str(); 8].join("/");
+        let file = dir.file(&format!("{}/file.txt", rel), b"");
+        assert!(file.as_os_str().len() > 300);
+        let mut option = ListOption::default();
+        option.recursive(true).include_root(false);
+        let listed = option.try_list(dir.path()).unwrap();
+        assert_eq!(listed.len(), 9);
+        assert!(listed.contains(&file));
+        // the paths keep the form of the root
+        assert!(listed
+            .iter()
+            .all(|path| !path.to_string_lossy().starts_with(r"\\?\")));
+        assert!(fs::metadata(&listed[8]).is_ok());
     }
 }
@@ -0,0 +1,100 @@
+use std::path::Path;
+
+use crate::ListOption;
+
+/// filter entries by the length of their listed path, e.g. before copying a tree
+/// to a filesystem with a path length limit
+///
+/// the length is counted in utf-16 code units on windows, the unit of MAX_PATH,
+/// and in bytes elsewhere, for the path as listed, i.e. the root joined with the names under it
+impl ListOption {
+    /// list only entries whose path has at most this length, the limit included
+    pub fn max_path_len(&mut self, len: usize) -> &mut Self {
+        self.max_path_len = Some(len);
+        self
+    }
+
+    /// list only entries whose path has at least this length, the limit included
+    pub fn min_path_len(&mut self, len: usize) -> &mut Self {
+        self.min_path_len = Some(len);
+        self
+    }
+
+    /// list only entries whose path is longer than the limit, the limit excluded,
+    /// e.g. only_paths_longer_than(259) finds the paths beyond the classic windows MAX_PATH,
+    /// same as min_path_len(limit + 1)
+    pub fn only_paths_longer_than(&mut self, limit: usize) -> &mut Self {
+        self.min_path_len(limit.saturating_add(1))
+    }
+
+    pub(crate) fn show_path_len(&self, path: &Path) -> bool {
+        if self.min_path_len.is_none() && self.max_path_len.is_none() {
+            return true;
+        }
+        let len = path_len(path);
+        self.min_path_len.is_none_or(|min| len >= min)
+            && self.max_path_len.is_none_or(|max| len <= max)
+    }
+}
+
+#[cfg(windows)]
+fn path_len(path: &Path) -> usize {
+    use std::os::windows::ffi::OsStrExt;
+
+    path.as_os_str().encode_wide().count()
+}
+
+#[cfg(not(windows))]
+fn path_len(path: &Path) -> usize {
+    path.as_os_str().as_encoded_bytes().len()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use super::path_len;
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn path_len_units() {
+        assert_eq!(path_len(Path::new("abc")), 3);
+        #[cfg(windows)]
+        assert_eq!(path_len(Path::new("é😀")), 3);
+        #[cfg(not(windows))]
+        assert_eq!(path_len(Path::new("é😀")), 6);
+    }
+
+    #[test]
+    fn show_path_len_bounds_included() {
+        let mut option = ListOption::default();
+        assert!(option.show_path_len(Path::new("")));
+        option.max_path_len(3);
+        assert!(option.show_path_len(Path::new("abc")));
+        assert!(!option.show_path_len(Path::new("abcd")));
+        option.min_path_len(3);
+        assert!(option.show_path_len(Path::new("abc")));
+        assert!(!option.show_path_len(Path::new("ab")));
+        let mut option = ListOption::default();
+        option.only_paths_longer_than(3);
+        assert!(!option.show_path_len(Path::new("abc")));
+        assert!(option.show_path_len(Path::new("abcd")));
+        option.only_paths_longer_than(usize::MAX);
+        assert!(!option.show_path_len(Path::new("abcd")));
+    }
+
+    #[test]
+    fn lists_by_the_listed_path() {
+        let dir = TestDir::new();
+        dir.file("a", b"");
+        dir.file("long_name", b"");
+        let root = dir.path().as_os_str().len();
+        let mut option = ListOption::default();
+        option.include_root(false).max_path_len(root + 2);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a"]);
+        option
+            .max_path_len(usize::MAX)
+            .only_paths_longer_than(root + 2);
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["long_name"]);
+    }
+}
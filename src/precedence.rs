@@ -0,0 +1,111 @@
+use std::path::Path;
+
+use crate::{Candidate, ListOption};
+
+// the single rule combining the filters of an entry:
+// it must pass all the include filters, and must not be excluded unless it is reincluded,
+// so a reinclude rescues an entry from the excludes but never from the include filters
+//
+// the include filters are the kind, visibility, level, ext, suf, prefix, name, glob,
+// regex and filter_fn ones, the excludes are exclude_ext, exclude_suf, exclude_dir,
+// exclude_path, prune_if and the ignore files, with the entries under an excluded directory
+pub(crate) fn resolve(included: bool, excluded: bool, reincluded: bool) -> bool {
+    included && (!excluded || reincluded)
+}
+
+// the reinclude patterns, which need the glob feature
+impl ListOption {
+    #[cfg(feature = "glob")]
+    pub(crate) fn has_reincludes(&self) -> bool {
+        !self.reincludes.is_empty()
+    }
+
+    #[cfg(not(feature = "glob"))]
+    pub(crate) fn has_reincludes(&self) -> bool {
+        false
+    }
+
+    // check if the entry is rescued from the excludes
+    #[cfg(feature = "glob")]
+    pub(crate) fn is_reincluded(&self, candidate: &Candidate) -> bool {
+        candidate
+            .rel_path
+            .is_some_and(|rel_path| self.reincludes.is_any_match(rel_path, self.ignore_case))
+    }
+
+    #[cfg(not(feature = "glob"))]
+    pub(crate) fn is_reincluded(&self, _candidate: &Candidate) -> bool {
+        false
+    }
+
+    // check if an excluded directory must still be read, for some entry under it may be reincluded
+    #[cfg(feature = "glob")]
+    pub(crate) fn may_reinclude_under(&self, rel_path: Option<&Path>) -> bool {
+        rel_path.is_some_and(|rel_path| self.reincludes.may_match_under(rel_path, self.ignore_case))
+    }
+
+    #[cfg(not(feature = "glob"))]
+    pub(crate) fn may_reinclude_under(&self, _rel_path: Option<&Path>) -> bool {
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::resolve;
+    #[cfg(feature = "glob")]
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn resolve_table() {
+        // included, excluded, reincluded, listed
+        let table = [
+            (false, false, false, false),
+            (false, false, true, false),
+            (false, true, false, false),
+            (false, true, true, false),
+            (true, false, false, true),
+            (true, false, true, true),
+            (true, true, false, false),
+            (true, true, true, true),
+        ];
+        for (included, excluded, reincluded, listed) in table {
+            assert_eq!(
+                resolve(included, excluded, reincluded),
+                listed,
+                "included {}, excluded {}, reincluded {}",
+                included,
+                excluded,
+                reincluded
+            );
+        }
+    }
+
+    #[cfg(feature = "glob")]
+    #[test]
+    fn reinclude_rescues_from_excludes_only() {
+        let dir = TestDir::new();
+        for path in [
+            "src/main.rs",
+            "target/doc/index.html",
+            "target/doc/app.js",
+            "target/out.html",
+        ] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .only_file()
+            .exclude_dir("target")
+            .reinclude("target/doc/**/*.html")
+            .unwrap();
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["src/main.rs", "target/doc/index.html"]
+        );
+        // a reinclude never lists what the include filters reject
+        option.ext("rs");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["src/main.rs"]);
+    }
+}
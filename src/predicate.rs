@@ -0,0 +1,98 @@
+use std::{fmt, path::Path, sync::Arc};
+
+use crate::ListOption;
+
+// a predicate set on a ListOption, shared between its clones
+//
+// two predicates are equal only if they are the same closure
+#[derive(Clone)]
+pub(crate) struct Predicate(Arc<dyn Fn(&Path) -> bool + Send + Sync>);
+
+impl Predicate {
+    pub(crate) fn new<F>(f: F) -> Self
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        Self(Arc::new(f))
+    }
+
+    pub(crate) fn test(&self, path: &Path) -> bool {
+        (self.0)(path)
+    }
+}
+
+impl fmt::Debug for Predicate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Predicate(..)")
+    }
+}
+
+impl PartialEq for Predicate {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Predicate {}
+
+/// filter entries by custom logic
+impl ListOption {
+    /// add a predicate an entry must pass to be listed
+    ///
+    /// it is consulted after all the built-in filters pass, with the path as it is listed,
+    /// and the entries are kept only if every predicate added returns true
+    ///
+    /// a directory rejected by it is still descended into, see [`ListOption::prune_if`] to skip it
+    pub fn filter_fn<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        self.filters.push(Predicate::new(f));
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn filters_are_asked_after_the_built_in_ones() {
+        let dir = TestDir::new();
+        for path in ["a.rs", "b.txt", "big.rs", "sub/c.rs"] {
+            dir.file(path, b"");
+        }
+        std::fs::write(dir.join("big.rs"), [0; 100]).unwrap();
+        let asked = Arc::new(AtomicUsize::new(0));
+        let counter = asked.clone();
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .ext("rs")
+            .max_size(10)
+            .filter_fn(move |_| {
+                counter.fetch_add(1, Ordering::Relaxed);
+                true
+            })
+            .filter_fn(|path| !path.ends_with("sub"));
+        // a directory rejected is still descended into
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["a.rs", "sub/c.rs"]);
+        // neither b.txt nor big.rs
+        assert_eq!(asked.load(Ordering::Relaxed), 3);
+    }
+
+    #[test]
+    fn predicates_are_equal_only_to_their_clones() {
+        let mut option = ListOption::default();
+        option.filter_fn(|_| true);
+        assert_eq!(option.clone(), option);
+        assert_ne!(option, *ListOption::default().filter_fn(|_| true));
+        assert!(format!("{:?}", option).contains("Predicate(..)"));
+    }
+}
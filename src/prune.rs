@@ -0,0 +1,230 @@
+use std::path::{self, Component, Path, PathBuf};
+
+use crate::{predicate::Predicate, Candidate, ListOption};
+
+/// exclude whole subtrees from the listing
+impl ListOption {
+    /// append one path to the excluded paths
+    ///
+    /// the path and everything under it are neither listed nor descended into,
+    /// a relative path is relative to the listing root, e.g. exclude_path("out") with list("proj")
+    /// skips proj/out, while an absolute path is compared with the absolute form of the entries
+    ///
+    /// the paths are compared by their components, so trailing slashes and `./` don't matter
+    pub fn exclude_path<P>(&mut self, path: P) -> &mut Self
+    where
+        P: AsRef<Path>,
+    {
+        let path = normalize(path.as_ref());
+        if !self.exclude_paths.contains(&path) {
+            self.exclude_paths.push(path);
+        }
+        self
+    }
+
+    /// replace the excluded paths with these, see [`ListOption::exclude_path`]
+    pub fn exclude_paths<I, P>(&mut self, paths: I) -> &mut Self
+    where
+        I: IntoIterator<Item = P>,
+        P: AsRef<Path>,
+    {
+        self.exclude_paths.clear();
+        for path in paths {
+            self.exclude_path(path);
+        }
+        self
+    }
+
+    /// add a predicate deciding which directories to skip along with their subtrees
+    ///
+    /// it is called with each directory under the root before it is read,
+    /// and when any predicate added returns true, the directory is neither listed nor read,
+    /// e.g. prune_if(|path| path.join(".git").exists()) skips nested repositories
+    pub fn prune_if<F>(&mut self, f: F) -> &mut Self
+    where
+        F: Fn(&Path) -> bool + Send + Sync + 'static,
+    {
+        self.prune_fns.push(Predicate::new(f));
+        self
+    }
+
+    // check if the candidate is excluded along with its subtree
+    pub(crate) fn prunes(&self, candidate: &Candidate) -> bool {
+        self.is_excluded_dir(candidate.names, candidate.path, candidate.metadata)
+            || (!self.hidden && !self.descend_hidden && self.is_hidden_dir(candidate))
+            || self.is_excluded_path(candidate)
+            || (candidate.metadata.is_dir()
+                && self.prune_fns.iter().any(|f| f.test(candidate.path)))
+    }
+
+    fn is_excluded_path(&self, candidate: &Candidate) -> bool {
+        if self.exclude_paths.is_empty() {
+            return false;
+        }
+        let rel_path = candidate.rel_path.map(normalize);
+        let abs_path = path::absolute(candidate.path)
+            .ok()
+            .map(|path| resolve_parents(&path));
+        // path::absolute keeps the `..` of a relative root, resolved above as the path reads,
+        // and by the canonical root as the filesystem does, which differ across symlinks
+        let canonical_path = candidate
+            .abs_root
+            .zip(rel_path.as_ref())
+            .map(|(root, rel_path)| root.join(rel_path));
+        self.exclude_paths.iter().any(|excluded| {
+            if excluded.is_absolute() {
+                [&abs_path, &canonical_path]
+                    .into_iter()
+                    .flatten()
+                    .any(|path| path.starts_with(excluded))
+            } else {
+                rel_path
+                    .as_ref()
+                    .is_some_and(|path| path.starts_with(excluded))
+            }
+        })
+    }
+}
+
+// drop the `.` components, which don't change where a path points to
+fn normalize(path: &Path) -> PathBuf {
+    path.components()
+        .filter(|component| *component != Component::CurDir)
+        .collect()
+}
+
+// drop the `.` components and each `..` with the component before it, as the path reads
+fn resolve_parents(path: &Path) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            // `..` at the root stays at the root
+            Component::ParentDir
+                if resolved.has_root()
+                    || matches!(
+                        resolved.components().next_back(),
+                        Some(Component::Normal(_))
+                    ) =>
+            {
+                resolved.pop();
+            }
+            component => resolved.push(component),
+        }
+    }
+    resolved
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{self, Path};
+    #[cfg(unix)]
+    use std::path::{Component, PathBuf};
+
+    use super::{normalize, resolve_parents};
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn normalize_drops_cur_dirs() {
+        assert_eq!(normalize(Path::new("./out/")), Path::new("out"));
+        assert_eq!(normalize(Path::new("a/./b")), Path::new("a/b"));
+        assert_eq!(normalize(Path::new("a/../b")), Path::new("a/../b"));
+    }
+
+    #[test]
+    fn resolve_parents_as_the_path_reads() {
+        assert_eq!(resolve_parents(Path::new("/a/./b/../c")), Path::new("/a/c"));
+        assert_eq!(resolve_parents(Path::new("/a/../../b")), Path::new("/b"));
+        assert_eq!(resolve_parents(Path::new("../a/..")), Path::new(".."));
+    }
+
+    #[test]
+    fn excluded_paths_relative_and_absolute() {
+        let dir = TestDir::new();
+        for path in ["out/a", "src/out/b", "src/gen/c", "src/main.rs"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .exclude_path("./out/")
+            .exclude_path("src/gen");
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["src", "src/main.rs", "src/out", "src/out/b"]
+        );
+        let absolute = path::absolute(dir.join("src/out")).unwrap();
+        option.exclude_paths([absolute, dir.join("src/main.rs")]);
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["out", "out/a", "src", "src/gen", "src/gen/c"]
+        );
+        // the same listing through a relative root, which climbs out of the working directory
+        #[cfg(unix)]
+        {
+            let cwd = std::env::current_dir().unwrap();
+            let mut root: PathBuf = cwd
+                .components()
+                .skip(1)
+                .map(|_| Component::ParentDir)
+                .collect();
+            root.push(dir.path().strip_prefix("/").unwrap());
+            assert!(root.starts_with(".."));
+            let listed = option
+                .list_paths(&root)
+                .iter()
+                .map(|path| dir.path().join(path.strip_prefix(&root).unwrap()))
+                .collect();
+            assert_eq!(
+                dir.rel(listed),
+                ["out", "out/a", "src", "src/gen", "src/gen/c"]
+            );
+        }
+    }
+
+    #[test]
+    fn prune_if_skips_subtrees_before_reading_them() {
+        use std::sync::{Arc, Mutex};
+
+        let dir = TestDir::new();
+        for path in [
+            "app/src/a.rs",
+            "app/vendor/lib/.git/HEAD",
+            "app/vendor/lib/b.rs",
+            "c.rs",
+        ] {
+            dir.file(path, b"");
+        }
+        let asked = Arc::new(Mutex::new(Vec::new()));
+        let record = asked.clone();
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .hidden(true)
+            .include_root(false)
+            .prune_if(move |path| {
+                record.lock().unwrap().push(path.to_path_buf());
+                path.join(".git").exists()
+            });
+        assert_eq!(
+            dir.rel(option.list_paths(dir.path())),
+            ["app", "app/src", "app/src/a.rs", "app/vendor", "c.rs"]
+        );
+        // only the directories under the root are checked
+        let asked = asked.lock().unwrap().clone();
+        assert_eq!(
+            dir.rel(asked),
+            ["app", "app/src", "app/vendor", "app/vendor/lib"]
+        );
+        // unlike filter_fn, which still descends into the rejected directories
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .include_root(false)
+            .filter_fn(|path| !path.join(".git").exists());
+        assert!(option
+            .list_paths(dir.path())
+            .contains(&dir.join("app/vendor/lib/b.rs")));
+    }
+}
@@ -0,0 +1,113 @@
+use std::path::Path;
+
+use regex::bytes::Regex;
+
+use crate::ListOption;
+
+pub use regex::Error as RegexError;
+
+// a compiled regex set on a ListOption, compared by its source
+#[derive(Clone, Debug)]
+pub(crate) struct Pattern(Regex);
+
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Self) -> bool {
+        self.0.as_str() == other.0.as_str()
+    }
+}
+
+impl Eq for Pattern {}
+
+impl Pattern {
+    // match on the raw bytes, so names which are not valid utf-8 are fine
+    pub(crate) fn is_match(&self, s: &[u8]) -> bool {
+        self.0.is_match(s)
+    }
+
+    pub(crate) fn is_path_match(&self, path: &Path) -> bool {
+        self.is_match(path.as_os_str().as_encoded_bytes())
+    }
+}
+
+/// filter entries by regex, needs the `regex` feature
+impl ListOption {
+    /// set the regex the file name of an entry must match to be listed, replacing the one set before
+    ///
+    /// the regex is not anchored, e.g. name_regex(r"\d") lists any name containing a digit,
+    /// use `^` and `$` to match the whole name, e.g. name_regex(r"^app\.\d{4}-\d{2}-\d{2}\.log$")
+    ///
+    /// the regex is compiled here, so an invalid one fails now instead of while listing
+    pub fn name_regex(&mut self, regex: &str) -> Result<&mut Self, RegexError> {
+        self.name_regex = Some(Pattern(Regex::new(regex)?));
+        Ok(self)
+    }
+
+    /// set the regex the whole path of an entry, as it is listed, must match to be listed,
+    /// replacing the one set before, see [`ListOption::name_regex`]
+    pub fn path_regex(&mut self, regex: &str) -> Result<&mut Self, RegexError> {
+        self.path_regex = Some(Pattern(Regex::new(regex)?));
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_dir::TestDir, EntryKind, ListOption};
+
+    #[test]
+    fn name_regex_is_not_anchored() {
+        let mut option = ListOption::default();
+        option.name_regex(r"\d").unwrap();
+        assert!(option.would_show_name("v2.txt", EntryKind::File));
+        assert!(!option.would_show_name("readme", EntryKind::File));
+        option.name_regex(r"^app\.\d{4}-\d{2}-\d{2}\.log$").unwrap();
+        assert!(option.would_show_name("app.2024-01-31.log", EntryKind::File));
+        assert!(!option.would_show_name("app.2024-01-31.log.gz", EntryKind::File));
+        // the name filters keep the case, even with case_insensitive
+        option.case_insensitive(true);
+        assert!(!option.would_show_name("APP.2024-01-31.log", EntryKind::File));
+        option.name_regex(r"(?i)^app").unwrap();
+        assert!(option.would_show_name("APP.2024-01-31.log", EntryKind::File));
+    }
+
+    #[test]
+    fn path_regex_on_the_listed_path() {
+        let dir = TestDir::new();
+        for path in ["src/a.rs", "tests/b.rs", "src/c.txt"] {
+            dir.file(path, b"");
+        }
+        let mut option = ListOption::default();
+        option
+            .recursive(true)
+            .only_file()
+            .path_regex(r"[/\\]src[/\\].*\.rs$")
+            .unwrap();
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["src/a.rs"]);
+    }
+
+    #[test]
+    fn invalid_regexes_fail_when_set() {
+        let mut option = ListOption::default();
+        assert!(option.name_regex("(").is_err());
+        assert!(option.path_regex("[a").is_err());
+        assert_eq!(option, ListOption::default());
+        option.name_regex("a").unwrap();
+        assert_eq!(option, *ListOption::default().name_regex("a").unwrap());
+        assert_ne!(option, *ListOption::default().name_regex("b").unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_names_are_matched_as_bytes() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let dir = TestDir::new();
+        let name = OsStr::from_bytes(b"bad\xff.log");
+        std::fs::write(dir.path().join(name), b"").unwrap();
+        let mut option = ListOption::default();
+        option.include_root(false).name_regex(r"^bad").unwrap();
+        assert_eq!(option.list_paths(dir.path()), [dir.path().join(name)]);
+        option.name_regex(r"\.log$").unwrap();
+        assert_eq!(option.count(dir.path()), 1);
+    }
+}
@@ -0,0 +1,177 @@
+use std::path::{Path, PathBuf};
+
+use crate::{ListError, ListOption, TryListIter};
+
+/// the limits which cut a listing short, see [`ListOption::try_list_report`]
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Truncation {
+    pub(crate) depth_limited_dirs: Vec<PathBuf>,
+    pub(crate) max_results_reached: bool,
+    pub(crate) truncated_dirs: Vec<PathBuf>,
+    pub(crate) too_long_paths: Vec<PathBuf>,
+    pub(crate) timed_out: bool,
+}
+
+impl Truncation {
+    /// check if some limit left anything out
+    pub fn is_truncated(&self) -> bool {
+        !self.depth_limited_dirs.is_empty()
+            || self.max_results_reached
+            || !self.truncated_dirs.is_empty()
+            || !self.too_long_paths.is_empty()
+            || self.timed_out
+    }
+
+    /// the directories with some entry in them, not read as they are at the depth limit,
+    /// see [`ListOption::level`]
+    pub fn depth_limited_dirs(&self) -> &[PathBuf] {
+        &self.depth_limited_dirs
+    }
+
+    /// if [`ListOption::max_results`] stopped the listing while some entry was left
+    pub fn max_results_reached(&self) -> bool {
+        self.max_results_reached
+    }
+
+    /// the directories cut short by [`ListOption::max_entries_per_dir`]
+    pub fn truncated_dirs(&self) -> &[PathBuf] {
+        &self.truncated_dirs
+    }
+
+    /// the paths skipped as too long for the system, see [`TryListIter::too_long_paths`]
+    pub fn too_long_paths(&self) -> &[PathBuf] {
+        &self.too_long_paths
+    }
+
+    /// if [`ListOption::timeout`] stopped the listing
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+}
+
+/// list along with what was left out
+impl ListOption {
+    /// same as [`ListOption::try_list`], but also reports the limits which cut the listing short,
+    /// so that "that's everything" can be told from "stopped early"
+    ///
+    /// a directory at the depth limit is read to tell if it has any entry,
+    /// and max_results is only reported as reached when some entry is left after it
+    pub fn try_list_report<P>(&self, path: P) -> Result<(Vec<PathBuf>, Truncation), ListError>
+    where
+        P: AsRef<Path>,
+    {
+        self.validate()?;
+        let mut iter = TryListIter::reporting(self, path);
+        let mut paths = Vec::new();
+        let mut error = None;
+        for res in iter.by_ref() {
+            match res {
+                Ok(path) => paths.push(path),
+                Err(err) => {
+                    error.get_or_insert(err);
+                }
+            }
+        }
+        match error {
+            Some(err) => Err(err),
+            None => Ok((paths, iter.into_truncation())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::{test_dir::TestDir, ListOption};
+
+    fn tree() -> TestDir {
+        let dir = TestDir::new();
+        for path in ["a.txt", "b.txt", "c.txt", "full/d.txt"] {
+            dir.file(path, b"");
+        }
+        std::fs::create_dir(dir.join("empty")).unwrap();
+        dir
+    }
+
+    #[test]
+    fn nothing_truncated() {
+        let dir = tree();
+        let mut option = ListOption::default();
+        option.recursive(true);
+        let (paths, truncation) = option.try_list_report(dir.path()).unwrap();
+        assert_eq!(paths.len(), 7);
+        assert!(!truncation.is_truncated());
+    }
+
+    #[test]
+    fn depth_limited_dirs_with_entries_only() {
+        let dir = tree();
+        let (paths, truncation) = ListOption::default().try_list_report(dir.path()).unwrap();
+        assert_eq!(paths.len(), 6);
+        assert_eq!(truncation.depth_limited_dirs(), [dir.join("full")]);
+        assert!(!truncation.max_results_reached());
+        assert!(truncation.truncated_dirs().is_empty());
+        assert!(truncation.is_truncated());
+    }
+
+    #[test]
+    fn max_results_reached_only_when_some_entry_is_left() {
+        let dir = tree();
+        let mut option = ListOption::default();
+        option.recursive(true).max_results(7);
+        let (paths, truncation) = option.try_list_report(dir.path()).unwrap();
+        assert_eq!(paths.len(), 7);
+        assert!(!truncation.is_truncated());
+        option.max_results(6);
+        let (paths, truncation) = option.try_list_report(dir.path()).unwrap();
+        assert_eq!(paths.len(), 6);
+        assert!(truncation.max_results_reached());
+        assert!(truncation.depth_limited_dirs().is_empty());
+        assert!(truncation.is_truncated());
+    }
+
+    #[test]
+    fn truncated_dirs_by_the_per_dir_cap() {
+        let dir = tree();
+        let mut option = ListOption::default();
+        option.recursive(true).max_entries_per_dir(2);
+        let (_, truncation) = option.try_list_report(dir.path()).unwrap();
+        assert_eq!(truncation.truncated_dirs(), [dir.path()]);
+        assert!(!truncation.max_results_reached());
+        assert!(truncation.is_truncated());
+        // a directory with as many entries as the cap is whole
+        option.max_entries_per_dir(5);
+        let (paths, truncation) = option.try_list_report(dir.path()).unwrap();
+        assert_eq!(paths.len(), 7);
+        assert!(!truncation.is_truncated());
+    }
+
+    #[test]
+    fn timed_out() {
+        let dir = tree();
+        let mut option = ListOption::default();
+        option.recursive(true).timeout(Duration::ZERO);
+        let (_, truncation) = option.try_list_report(dir.path()).unwrap();
+        assert!(truncation.timed_out());
+        assert!(truncation.depth_limited_dirs().is_empty());
+        assert!(truncation.is_truncated());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn too_long_paths() {
+        let dir = TestDir::new();
+        dir.chain(3000);
+        let mut option = ListOption::default();
+        option.recursive(true);
+        let (paths, truncation) = option.try_list_report(dir.path()).unwrap();
+        // the rest of the chain is beyond PATH_MAX on most systems
+        if paths.len() <= 3000 {
+            assert_eq!(truncation.too_long_paths().len(), 1);
+            assert!(truncation.is_truncated());
+        }
+        assert!(truncation.depth_limited_dirs().is_empty());
+        assert!(!truncation.timed_out());
+    }
+}
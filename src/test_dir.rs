@@ -0,0 +1,136 @@
+#[cfg(unix)]
+use std::cell::RefCell;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// a directory made for a test under the temp dir, removed with all its entries when dropped
+pub(crate) struct TestDir {
+    path: PathBuf,
+    // the directories made unreadable, made readable again before they are removed
+    #[cfg(unix)]
+    locked: RefCell<Vec<PathBuf>>,
+}
+
+impl TestDir {
+    pub(crate) fn new() -> Self {
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let name = format!(
+            "ls-option-{}-{}",
+            std::process::id(),
+            COUNT.fetch_add(1, Ordering::Relaxed)
+        );
+        let path = std::env::temp_dir().join(name);
+        fs::create_dir_all(&path).unwrap();
+        Self {
+            path,
+            #[cfg(unix)]
+            locked: RefCell::new(Vec::new()),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn join(&self, rel: &str) -> PathBuf {
+        self.path.join(rel)
+    }
+
+    // make the file with its parents, returning its path
+    pub(crate) fn file(&self, rel: &str, content: &[u8]) -> PathBuf {
+        let path = self.join(rel);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, content).unwrap();
+        path
+    }
+
+    // make a symlink at rel pointing to target, as it is given
+    #[cfg(unix)]
+    pub(crate) fn symlink(&self, target: &str, rel: &str) -> PathBuf {
+        let path = self.join(rel);
+        std::os::unix::fs::symlink(target, &path).unwrap();
+        path
+    }
+
+    // make the directory at rel unreadable, with mode 000
+    #[cfg(unix)]
+    pub(crate) fn lock(&self, rel: &str) -> PathBuf {
+        self.lock_with_mode(rel, 0o000)
+    }
+
+    // make the directory at rel unreadable with this mode, e.g. 311 to still search it
+    #[cfg(unix)]
+    pub(crate) fn lock_with_mode(&self, rel: &str, mode: u32) -> PathBuf {
+        use std::os::unix::fs::PermissionsExt;
+        let path = self.join(rel);
+        fs::create_dir_all(&path).unwrap();
+        fs::set_permissions(&path, fs::Permissions::from_mode(mode)).unwrap();
+        self.locked.borrow_mut().push(path.clone());
+        path
+    }
+
+    // make a chain of `d` directories this deep, by relative calls
+    // as the full paths get beyond what the system allows
+    #[cfg(unix)]
+    pub(crate) fn chain(&self, depth: usize) {
+        use std::{
+            ffi::CString,
+            os::unix::ffi::OsStrExt,
+            os::unix::io::{AsRawFd, FromRawFd, OwnedFd},
+        };
+
+        let root = CString::new(self.path.as_os_str().as_bytes()).unwrap();
+        let name = CString::new("d").unwrap();
+        let flags = libc::O_RDONLY | libc::O_DIRECTORY | libc::O_CLOEXEC;
+        // SAFETY: `root` is a nul terminated string which outlives the call
+        let raw = unsafe { libc::open(root.as_ptr(), flags) };
+        assert!(raw >= 0);
+        // SAFETY: `raw` was just opened and is owned by nothing else
+        let mut dir = unsafe { OwnedFd::from_raw_fd(raw) };
+        for _ in 0..depth {
+            // SAFETY: `dir` is an open directory and `name` a nul terminated string,
+            // and the fd is closed by its owner whichever way this returns
+            let made = unsafe { libc::mkdirat(dir.as_raw_fd(), name.as_ptr(), 0o755) };
+            assert_eq!(made, 0);
+            // SAFETY: as above
+            let raw = unsafe { libc::openat(dir.as_raw_fd(), name.as_ptr(), flags) };
+            assert!(raw >= 0);
+            // SAFETY: `raw` was just opened and is owned by nothing else
+            dir = unsafe { OwnedFd::from_raw_fd(raw) };
+        }
+    }
+
+    // the paths under it, relative to it, sorted
+    pub(crate) fn rel(&self, paths: Vec<PathBuf>) -> Vec<String> {
+        let mut rel: Vec<_> = paths
+            .iter()
+            .map(|path| {
+                let rel = path.strip_prefix(&self.path).unwrap();
+                rel.to_string_lossy().replace('\\', "/")
+            })
+            .collect();
+        rel.sort();
+        rel
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        #[cfg(unix)]
+        for path in self.locked.borrow().iter() {
+            use std::os::unix::fs::PermissionsExt;
+            let _ = fs::set_permissions(path, fs::Permissions::from_mode(0o755));
+        }
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+// root reads any directory whatever its mode, so the tests on unreadable ones can't run as it
+#[cfg(unix)]
+pub(crate) fn is_root() -> bool {
+    // SAFETY: geteuid takes nothing and always succeeds
+    unsafe { libc::geteuid() == 0 }
+}
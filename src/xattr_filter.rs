@@ -0,0 +1,87 @@
+use crate::{Candidate, ListError, ListOption};
+
+/// filter entries by their extended attributes, needs the `xattr` feature, unix only
+impl ListOption {
+    /// append one extended attribute the entries must carry to be listed,
+    /// e.g. has_xattr("com.apple.quarantine") lists the files downloaded on macOS
+    ///
+    /// only files and directories carrying all the asked attributes are listed,
+    /// and they are read only after the name and metadata filters pass
+    ///
+    /// a filesystem without extended attributes fails with `ListError::Metadata`,
+    /// handled by the error policy
+    pub fn has_xattr(&mut self, name: &str) -> &mut Self {
+        self.xattrs.push((name.to_string(), None));
+        self
+    }
+
+    /// append one extended attribute the entries must carry with exactly this value,
+    /// e.g. xattr_equals("user.backup", b"skip"), see [`ListOption::has_xattr`]
+    pub fn xattr_equals(&mut self, name: &str, value: &[u8]) -> &mut Self {
+        self.xattrs.push((name.to_string(), Some(value.to_vec())));
+        self
+    }
+}
+
+// the checks of the extended attributes of an entry
+impl ListOption {
+    pub(crate) fn show_xattrs(&self, candidate: &Candidate) -> Result<bool, ListError> {
+        if self.xattrs.is_empty() {
+            return Ok(true);
+        }
+        let metadata = candidate.metadata;
+        if !(metadata.is_file() || metadata.is_dir()) {
+            return Ok(false);
+        }
+        for (name, value) in &self.xattrs {
+            let found = xattr::get(candidate.path, name).map_err(|source| ListError::Metadata {
+                path: candidate.path.to_path_buf(),
+                source,
+            })?;
+            let matched = match (found, value) {
+                (Some(found), Some(value)) => found == *value,
+                (found, None) => found.is_some(),
+                (None, Some(_)) => false,
+            };
+            if !matched {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{test_dir::TestDir, ListOption};
+
+    #[test]
+    fn lists_by_the_attributes_carried() {
+        let dir = TestDir::new();
+        let tagged = dir.file("tagged", b"");
+        let other = dir.file("other", b"");
+        let untagged = dir.file("untagged", b"");
+        // the temp dir may be on a filesystem without user attributes
+        if xattr::set(&tagged, "user.backup", b"skip").is_err() {
+            return;
+        }
+        xattr::set(&other, "user.backup", b"keep").unwrap();
+        xattr::set(&other, "user.extra", b"").unwrap();
+        let mut option = ListOption::default();
+        option.include_root(false).has_xattr("user.backup");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["other", "tagged"]);
+        let mut option = ListOption::default();
+        option
+            .include_root(false)
+            .xattr_equals("user.backup", b"skip");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["tagged"]);
+        // all the attributes asked must be carried
+        let mut option = ListOption::default();
+        option
+            .include_root(false)
+            .has_xattr("user.backup")
+            .has_xattr("user.extra");
+        assert_eq!(dir.rel(option.list_paths(dir.path())), ["other"]);
+        assert!(!option.would_show(&untagged));
+    }
+}